@@ -6,13 +6,13 @@ extern crate rusthello_lib;
 extern crate test;
 
 use reversi::game::Game;
-use rusthello_lib::ai_player::AiPlayer;
+use rusthello_lib::ai_player::{AiPlayer, Strength};
 
 #[bench]
 fn test_ai(b: &mut test::Bencher) {
 
-    let adam = AiPlayer::Weak;
-    let eve  = AiPlayer::Weak;
+    let adam = AiPlayer::new(Strength::Weak);
+    let eve  = AiPlayer::new(Strength::Weak);
 
     b.iter(|| {
                // First match: Adam is Dark, Eve is Light
@@ -1,17 +1,32 @@
 //! Provides `game::IsPlayer<::OtherAction>` types.
 
 use {Result, Action};
-use rand::thread_rng;
+use rand::{thread_rng, SeedableRng, XorShiftRng};
 use rand::distributions::{IndependentSample, Range};
 use rayon::prelude::*;
 use reversi::{board, turn, game, Side, ReversiError};
 use reversi::board::Coord;
-use std::cmp::Ordering;
+use std::cmp::{self, Ordering};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
+use std::time::{Duration, Instant};
+use endgame_solver::{self, SolveMode, ENDGAME_EMPTY_CELLS};
+use evaluation::{Evaluate, Evaluator};
+use transposition_table::{Bound, Entry, TranspositionTable, DEFAULT_SIZE};
 
-const RANDOMNESS: f64 = 0.05f64;
-const WEAK:		u32 = 100;
-const MEDIUM:	u32 = 10000;
-const STRONG:	u32 = 1000000;
+/// Default random factor applied to evaluations.
+pub const RANDOMNESS: f64 = 0.05f64;
+const WEAK:		u8 = 2;
+const MEDIUM:	u8 = 4;
+const STRONG:	u8 = 6;
+
+/// Empty cells from which a medium search solves the endgame exactly: such solves take milliseconds.
+const MEDIUM_ENDGAME_EMPTY_CELLS: usize = 8;
+
+/// Lower bound to every possible `Score`, used to initialize alpha.
+const MIN_SCORE: Score = Score::Ended(i16::MIN);
+/// Upper bound to every possible `Score`, used to initialize beta.
+const MAX_SCORE: Score = Score::Ended(i16::MAX);
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Score {
@@ -21,275 +36,643 @@ pub enum Score {
 
 impl PartialOrd for Score {
     fn partial_cmp(&self, other: &Score) -> Option<Ordering> {
-        if match (*self, *other) {
-    		(Score::Running(val1), Score::Running(val2)) => val1 > val2,
-        	(Score::Running(val1), Score::Ended(scr2)) => scr2 < 0i16 || (scr2 == 0i16 && val1 > 0f64),
-        	(Score::Ended(scr1), Score::Running(val2)) => scr1 > 0i16 || (scr1 == 0i16 && val2 < 0f64),
-        	(Score::Ended(scr1), Score::Ended(scr2)) => scr1 > scr2,
-    	} {
-            Some(Ordering::Greater)
-        } else {
-            Some(Ordering::Less)
-        }
+        Some(self.cmp(other))
     }
 }
 
 impl Eq for Score {}
 
 impl Ord for Score {
+    /// Running scores are compared with ended ones by their sign, so that a draw is better than any losing evaluation
+    /// and worse than any winning one. A draw is better than an evaluation of exactly zero,
+    /// so that the order stays total and antisymmetric.
     fn cmp(&self, other: &Score) -> Ordering {
         if *self == *other {
             Ordering::Equal
+        } else if match (*self, *other) {
+    		(Score::Running(val1), Score::Running(val2)) => val1 > val2,
+        	(Score::Running(val1), Score::Ended(scr2)) => scr2 < 0i16 || (scr2 == 0i16 && val1 > 0f64),
+        	(Score::Ended(scr1), Score::Running(val2)) => scr1 > 0i16 || (scr1 == 0i16 && val2 <= 0f64),
+        	(Score::Ended(scr1), Score::Ended(scr2)) => scr1 > scr2,
+    	} {
+            Ordering::Greater
         } else {
-            self.partial_cmp(other).expect("Should be ordered")
+            Ordering::Less
         }
     }
 }
 
-pub enum AiPlayer {
+/// How much an `AlphaBeta` search looks ahead before moving.
+/// Weak searches never solve the endgame exactly, medium ones solve the last few moves,
+/// and strong and timed ones switch to the solver at `ENDGAME_EMPTY_CELLS`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Strength {
     Weak,
     Medium,
     Strong,
+    /// Searches by iterative deepening for the given time per move.
+    Timed(Duration),
+}
+
+impl Strength {
+    /// Empty cells from which searches of this strength solve the endgame exactly.
+    fn endgame_empty_cells(&self) -> usize {
+        match *self {
+            Strength::Weak => 0,
+            Strength::Medium => MEDIUM_ENDGAME_EMPTY_CELLS,
+            Strength::Strong | Strength::Timed(_) => ENDGAME_EMPTY_CELLS,
+        }
+    }
+}
+
+/// A way of searching the game tree for the best move, relying on an evaluator to judge positions.
+pub trait Search {
+    /// Finds the best move of `turn`, evaluating positions with `evaluator`.
+    fn best_move(&self, turn: &turn::Turn, evaluator: &Evaluate) -> Result<Coord>;
+}
+
+/// An AI player, made of a search and an evaluator which can be paired at will.
+pub struct AiPlayer<S = AlphaBeta, E = Evaluator> {
+    search: S,
+    evaluator: E,
 }
 
-impl game::IsPlayer<::OtherAction> for AiPlayer {
-    /// Calls `find_best_move` with suitable parameters
+impl<S: Search, E: Evaluate> game::IsPlayer<::OtherAction> for AiPlayer<S, E> {
+    /// Lets the search find the best move according to the evaluator
     fn make_move(&self, turn: &turn::Turn) -> Result<Action> {
-        Ok(game::PlayerAction::Move(try!(match *self {
-                                             AiPlayer::Weak => AiPlayer::find_best_move(turn, WEAK),
-                                             AiPlayer::Medium => AiPlayer::find_best_move(turn, MEDIUM),
-                                             AiPlayer::Strong => AiPlayer::find_best_move(turn, STRONG),
-                                         })))
+        Ok(game::PlayerAction::Move(self.search.best_move(turn, &self.evaluator)?))
     }
 }
 
 impl AiPlayer {
+    /// Creates a player of the given strength, evaluating positions with the default weights.
+    pub fn new(strength: Strength) -> AiPlayer {
+        AiPlayer::with_evaluator(strength, Evaluator::default())
+    }
+
+    /// Creates a player of the given strength, evaluating positions with `evaluator`.
+    pub fn with_evaluator(strength: Strength, evaluator: Evaluator) -> AiPlayer {
+        AiPlayer::compose(AlphaBeta::new(strength), evaluator)
+    }
+}
+
+impl<S: Search, E: Evaluate> AiPlayer<S, E> {
+    /// Creates a player pairing any search with any evaluator.
+    pub fn compose(search: S, evaluator: E) -> AiPlayer<S, E> {
+        AiPlayer {
+            search,
+            evaluator,
+        }
+    }
+}
+
+/// The analysis of one of the legal moves of a position.
+#[derive(Debug, Clone)]
+pub struct MoveAnalysis {
+    pub coord: Coord,
+    pub score: Score,
+    /// The moves expected to follow under best play, starting with `coord` itself.
+    /// It can be shorter than the search depth, if some positions have been pushed out of the transposition table.
+    pub variation: Vec<Coord>,
+}
+
+/// The analysis of a position: all of its legal moves, with their scores.
+#[derive(Debug, Clone)]
+pub struct Analysis {
+    /// The legal moves, ranked from the best to the worst for the side to move.
+    pub moves: Vec<MoveAnalysis>,
+    /// How many plies deep the moves have been searched.
+    /// If it is at least the number of empty cells, all scores are exact.
+    pub depth: u8,
+}
+
+/// Alpha-beta search with a transposition table, handing over to the exact solver near the end of the game
+/// unless it is weak (see `Strength`).
+/// The table is kept from one search to the next, so that the results of searching a move carry over to the following moves.
+/// Clones share the same table.
+#[derive(Debug, Clone)]
+pub struct AlphaBeta {
+    strength: Strength,
+    /// Evaluations are scaled by a random factor within this range, so that play is less predictable.
+    randomness: f64,
+    /// If set, the search is deterministic (see `AlphaBeta::seeded`).
+    seed: Option<u64>,
+    /// At or below this number of empty cells, the endgame is solved exactly rather than searched to a fixed depth.
+    endgame_empty_cells: usize,
+    /// Only unrandomized scores are stored, so the table only depends on the evaluator:
+    /// a search should always be used with the same one.
+    table: Arc<TranspositionTable>,
+}
+
+impl Search for AlphaBeta {
+    /// Calls `best_move_and_score` and forgets about the score
+    fn best_move(&self, turn: &turn::Turn, evaluator: &Evaluate) -> Result<Coord> {
+        self.best_move_and_score(turn, evaluator).map(|(coord, _)| coord)
+    }
+}
+
+/// Time limit, transposition table and evaluator of a search, shared by all the threads working on it.
+struct SearchContext<'a> {
+    table: &'a TranspositionTable,
+    evaluator: &'a Evaluate,
+    deadline: Option<Instant>,
+    expired: AtomicBool,
+}
+
+impl<'a> SearchContext<'a> {
+    fn new(table: &'a TranspositionTable, evaluator: &'a Evaluate, deadline: Option<Instant>) -> SearchContext<'a> {
+        SearchContext {
+            table,
+            evaluator,
+            deadline,
+            expired: AtomicBool::new(false),
+        }
+    }
+
+    /// Checks whether the search has run out of time.
+    /// Once expired, the limit stays expired so that every thread aborts as soon as possible.
+    fn is_expired(&self) -> bool {
+        if self.expired.load(AtomicOrdering::Relaxed) {
+            true
+        } else if self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            self.expired.store(true, AtomicOrdering::Relaxed);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Finds all legal moves for the current turn and returns their coordinates.
+pub fn legal_moves(turn: &turn::Turn) -> Vec<Coord> {
+    let mut moves: Vec<Coord> = Vec::new();
+    for row in 0..board::BOARD_SIZE {
+        for col in 0..board::BOARD_SIZE {
+            let coord = board::Coord::new(row, col);
+            if turn.check_move(coord).is_ok() {
+                moves.push(coord);
+            }
+        }
+    }
+    moves
+}
+
+/// Counts the empty cells left on the board of the current turn.
+pub fn empty_cells(turn: &turn::Turn) -> usize {
+    let (score_dark, score_light) = turn.get_score();
+    board::BOARD_SIZE * board::BOARD_SIZE - score_dark as usize - score_light as usize
+}
+
+/// Creates a random number generator depending only on `seed` and on the position of `turn`,
+/// so that deterministic players make the same choices whatever the order they are asked to move in.
+pub fn seeded_rng(seed: u64, turn: &turn::Turn) -> XorShiftRng {
+    // FNV-1a hash of the position, mixed with the seed
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325 ^ seed;
+    for row in 0..board::BOARD_SIZE {
+        for col in 0..board::BOARD_SIZE {
+            let cell = match *turn.get_cell(Coord::new(row, col)).expect("Coordinates are within the board") {
+                Some(disk) if disk.get_side() == Side::Dark => 1,
+                Some(_) => 2,
+                None => 3,
+            };
+            hash = (hash ^ cell).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    if turn.get_state() == Some(Side::Light) {
+        hash = (hash ^ 4).wrapping_mul(0x0100_0000_01b3);
+    }
+    // XorShift needs a seed which is not all zeros
+    XorShiftRng::from_seed([hash as u32, (hash >> 32) as u32, seed as u32, (seed >> 32) as u32 | 1])
+}
+
+impl AlphaBeta {
+    pub fn new(strength: Strength) -> AlphaBeta {
+        AlphaBeta::with_table_size(strength, DEFAULT_SIZE)
+    }
+
+    /// Creates a search whose transposition table has room for `size` entries.
+    pub fn with_table_size(strength: Strength, size: usize) -> AlphaBeta {
+        AlphaBeta {
+            strength,
+            randomness: RANDOMNESS,
+            seed: None,
+            endgame_empty_cells: strength.endgame_empty_cells(),
+            table: Arc::new(TranspositionTable::new(size)),
+        }
+    }
+
+    /// Makes the search solve the endgame exactly from `cells` empty cells on, instead of the strength's default
+    /// (0 never solves it). Without a time limit solves can take long above `ENDGAME_EMPTY_CELLS`, see its timings.
+    pub fn with_endgame_empty_cells(mut self, cells: usize) -> AlphaBeta {
+        self.endgame_empty_cells = cells;
+        self
+    }
+
+    /// Makes the search scale evaluations by a random factor within `randomness` (zero for none).
+    pub fn with_randomness(mut self, randomness: f64) -> AlphaBeta {
+        self.randomness = randomness;
+        self
+    }
+
+    /// The transposition table kept by the search, e.g. to read its hit and miss counters.
+    pub fn table(&self) -> &TranspositionTable {
+        &self.table
+    }
+
+    /// Creates a deterministic search, so that games can be replayed exactly.
+    /// The random factor applied to evaluations (within `randomness`, which can be zero)
+    /// only depends on `seed` and on the position, and moves are searched one after the other,
+    /// so the same sequence of positions always gets the same moves.
+    /// Only `Strength::Timed` searches still depend on the speed of the machine.
+    pub fn seeded(strength: Strength, seed: u64, randomness: f64) -> AlphaBeta {
+        AlphaBeta {
+            strength,
+            randomness,
+            seed: Some(seed),
+            endgame_empty_cells: strength.endgame_empty_cells(),
+            table: Arc::new(TranspositionTable::new(DEFAULT_SIZE)),
+        }
+    }
+
+    /// Searches as deep as the strength allows and returns the best move together with its score.
+    /// A forced move is not searched, so its score is just the evaluation of the position it leads to.
+    pub fn best_move_and_score(&self, turn: &turn::Turn, evaluator: &Evaluate) -> Result<(Coord, Score)> {
+        let table = &*self.table;
+        match self.strength {
+            Strength::Weak => self.search_to_depth(turn, WEAK, table, evaluator),
+            Strength::Medium => self.search_to_depth(turn, MEDIUM, table, evaluator),
+            Strength::Strong => self.search_to_depth(turn, STRONG, table, evaluator),
+            Strength::Timed(time) => self.search_in_time(turn, time, table, evaluator),
+        }
+    }
+
+    /// Analyzes all legal moves of `turn`, searching as deep as the strength allows.
+    pub fn analyze(&self, turn: &turn::Turn, evaluator: &Evaluate) -> Result<Analysis> {
+        let table = &*self.table;
+        match self.strength {
+            Strength::Weak => self.analyze_to_depth(turn, WEAK, table, evaluator),
+            Strength::Medium => self.analyze_to_depth(turn, MEDIUM, table, evaluator),
+            Strength::Strong => self.analyze_to_depth(turn, STRONG, table, evaluator),
+            Strength::Timed(time) => self.analyze_in_time(turn, time, table, evaluator),
+        }
+    }
+
+    /// Analyzes all legal moves of `turn` by an alpha-beta search `depth` plies deep.
+    /// Unlike `find_best_move`, scores are not randomized and every move gets an exact score.
+    /// Near the end of the game, unless the search is weak, moves are searched to the end,
+    /// so that their scores are the final disk differentials.
+    pub fn analyze_to_depth(&self, turn: &turn::Turn, depth: u8, table: &TranspositionTable, evaluator: &Evaluate) -> Result<Analysis> {
+        let depth = if empty_cells(turn) <= self.endgame_empty_cells {
+            empty_cells(turn) as u8
+        } else {
+            cmp::max(depth, 1)
+        };
+        self.analyze_root(turn, depth, &SearchContext::new(table, evaluator, None))
+    }
+
+    /// Analyzes all legal moves of `turn` by iterative deepening, until `time` runs out.
+    /// The returned analysis is the one of the deepest search which could be completed.
+    pub fn analyze_in_time(&self, turn: &turn::Turn, time: Duration, table: &TranspositionTable, evaluator: &Evaluate) -> Result<Analysis> {
+        let start = Instant::now();
+
+        // The shallowest search is always completed, so that there is an analysis to return
+        let mut analysis = self.analyze_root(turn, 1, &SearchContext::new(table, evaluator, None))?;
+
+        let context = SearchContext::new(table, evaluator, Some(start + time));
+        for depth in 2..(empty_cells(turn) as u8 + 1) {
+            let new_analysis = self.analyze_root(turn, depth, &context)?;
+            if context.is_expired() {
+                // The search was interrupted, so its result is not reliable
+                break;
+            }
+            analysis = new_analysis;
+        }
+        Ok(analysis)
+    }
+
+    fn analyze_root(&self, turn: &turn::Turn, depth: u8, context: &SearchContext) -> Result<Analysis> {
+        // If everything is alright, turn shouldn't be ended
+        let side = turn.get_state()
+            .ok_or(ReversiError::EndedGame(*turn))?;
+
+        let mut moves = self.map_moves(&legal_moves(turn), |&coord| {
+            let mut turn_after_move = *turn;
+            turn_after_move
+                .make_move(coord)
+                .expect("The move was checked, but something went wrong!");
+            let score = AlphaBeta::alpha_beta(&turn_after_move, depth - 1, MIN_SCORE, MAX_SCORE, context)
+                .expect("Something went wrong with `AlphaBeta::alpha_beta`!");
+            // The variation is read right away, before other searches replace its entries
+            let mut variation = vec![coord];
+            variation.extend(AlphaBeta::principal_variation(&turn_after_move, depth - 1, context.table));
+            MoveAnalysis {
+                coord,
+                score,
+                variation,
+            }
+        });
+
+        // Best moves first
+        moves.sort_by(|move1, move2| match side {
+                          Side::Light => move2.score.cmp(&move1.score),
+                          Side::Dark => move1.score.cmp(&move2.score),
+                      });
+
+        Ok(Analysis {
+               moves,
+               depth,
+           })
+    }
+
+    /// Follows the best moves stored in `table`, starting from `turn`, for at most `length` moves.
+    fn principal_variation(turn: &turn::Turn, length: u8, table: &TranspositionTable) -> Vec<Coord> {
+        let mut variation = Vec::new();
+        let mut turn = *turn;
+        while variation.len() < length as usize && turn.get_state().is_some() {
+            let coord = match table.probe(table.hash(&turn)).and_then(|entry| entry.best_move) {
+                Some(coord) => coord,
+                None => break,
+            };
+            // Entries could come from a colliding position, so moves are checked
+            if turn.make_move(coord).is_err() {
+                break;
+            }
+            variation.push(coord);
+        }
+        variation
+    }
+
     /// Find best moves among the legal ones.
-    /// Each possibility is evaluated by a method depending on the value of `self` and confronted with the others.
-    pub fn find_best_move(turn: &turn::Turn, comps: u32) -> Result<board::Coord> {
+    /// Each possibility is evaluated by an alpha-beta search `depth` plies deep and confronted with the others.
+    /// Search results are shared through `table`, which can be inspected afterwards.
+    /// Positions at the end of the search are evaluated by `evaluator`.
+    pub fn find_best_move(&self, turn: &turn::Turn, depth: u8, table: &TranspositionTable, evaluator: &Evaluate) -> Result<board::Coord> {
+        self.search_to_depth(turn, depth, table, evaluator).map(|(coord, _)| coord)
+    }
+
+    /// Find best moves among the legal ones by iterative deepening.
+    /// Searches deeper and deeper until `time` runs out,
+    /// then returns the best move found by the last search which could be completed.
+    /// Near the end of the game, the exact solver is tried first, for at most half of the time.
+    /// Each search uses the results of the previous ones, stored in `table`.
+    pub fn find_best_move_in_time(&self,
+                                  turn: &turn::Turn,
+                                  time: Duration,
+                                  table: &TranspositionTable,
+                                  evaluator: &Evaluate)
+                                  -> Result<board::Coord> {
+        self.search_in_time(turn, time, table, evaluator).map(|(coord, _)| coord)
+    }
+
+    /// Same as `find_best_move`, but returns the score of the best move too.
+    pub fn search_to_depth(&self, turn: &turn::Turn, depth: u8, table: &TranspositionTable, evaluator: &Evaluate) -> Result<(Coord, Score)> {
 
         // If everything is alright, turn shouldn't be ended
         let side = turn.get_state()
             .ok_or_else(|| ReversiError::EndedGame(*turn))?;
 
         // Finds all possible legal moves and records their coordinates
-        let mut moves: Vec<Coord> = Vec::new();
-        for row in 0..board::BOARD_SIZE {
-            for col in 0..board::BOARD_SIZE {
-                let coord = board::Coord::new(row, col);
-                if turn.check_move(coord).is_ok() {
-                    moves.push(coord);
-                }
-            }
+        let moves = legal_moves(turn);
+
+        match moves.len() {
+            0 => unreachable!("Game is not ended!"), // Game can't be ended
+            // If there is only one possible move, there's no point in searching it.
+            1 => AlphaBeta::forced_move(turn, moves[0], &SearchContext::new(table, evaluator, None)),
+            _num_moves if empty_cells(turn) <= self.endgame_empty_cells => AlphaBeta::solve_endgame(turn),
+            _num_moves => self.search_root(turn, side, &moves, depth, &SearchContext::new(table, evaluator, None)),
         }
+    }
+
+    /// Same as `find_best_move_in_time`, but returns the score of the best move too.
+    pub fn search_in_time(&self, turn: &turn::Turn, time: Duration, table: &TranspositionTable, evaluator: &Evaluate) -> Result<(Coord, Score)> {
+        let start = Instant::now();
+
+        // If everything is alright, turn shouldn't be ended
+        let side = turn.get_state()
+            .ok_or(ReversiError::EndedGame(*turn))?;
+
+        let moves = legal_moves(turn);
 
         match moves.len() {
             0 => unreachable!("Game is not ended!"), // Game can't be ended
-            1 => Ok(moves[0]), // If there is only one possible move, there's no point in evaluating it.
-            num_moves => {
-                // Each move has to be evaluated in order to find the best one
-                let moves_and_scores = moves
-                    .par_iter()
-                    .map(|&coord| {
-                        let mut turn_after_move = *turn;
-                        turn_after_move
-                            .make_move(coord)
-                            .expect("The move was checked, but something went wrong!");
-                        let score = AiPlayer::ai_eval(&turn_after_move, comps / num_moves as u32)
-                            .expect("Something went wrong with `AiPlayer::ai_eval`!");
-                        (coord, score)
-                    });
-                let best_move_and_score = match side {
-                        Side::Dark => moves_and_scores.min_by_key(|&(_, score)| score),
-                        Side::Light => moves_and_scores.max_by_key(|&(_, score)| score),
+            // If there is only one possible move, there's no point in searching it.
+            1 => AlphaBeta::forced_move(turn, moves[0], &SearchContext::new(table, evaluator, None)),
+            _num_moves => {
+                // Near the end of the game the solver gets half of the time, and heuristics the rest if it cannot finish
+                if empty_cells(turn) <= self.endgame_empty_cells {
+                    if let Some(solution) = endgame_solver::solve_in_time(turn, SolveMode::Exact, time / 2)? {
+                        let best_move = solution.best_move.ok_or(ReversiError::EndedGame(*turn))?;
+                        return Ok((best_move, solution.score));
+                    }
+                }
+
+                // The shallowest search is always completed, so that there is a move to return
+                let mut best_move_and_score = self.search_root(turn, side, &moves, 1, &SearchContext::new(table, evaluator, None))?;
+
+                // There is no point in searching deeper than the number of empty cells left
+                let context = SearchContext::new(table, evaluator, Some(start + time));
+                for depth in 2..(empty_cells(turn) as u8 + 1) {
+                    let new_best_move_and_score = self.search_root(turn, side, &moves, depth, &context)?;
+                    if context.is_expired() {
+                        // The search was interrupted, so its result is not reliable
+                        break;
                     }
-                    .expect("No best move found!");
-                Ok(best_move_and_score.0)
+                    best_move_and_score = new_best_move_and_score;
+                }
+                Ok(best_move_and_score)
+            }
+        }
+    }
+
+    /// Scores the only legal move by evaluating the position it leads to.
+    fn forced_move(turn: &turn::Turn, coord: Coord, context: &SearchContext) -> Result<(Coord, Score)> {
+        let mut turn_after_move = *turn;
+        turn_after_move.make_move(coord)?;
+        Ok((coord, AlphaBeta::alpha_beta(&turn_after_move, 0, MIN_SCORE, MAX_SCORE, context)?))
+    }
+
+    /// Near the end of the game, heuristics give way to a perfect-play solver.
+    fn solve_endgame(turn: &turn::Turn) -> Result<(Coord, Score)> {
+        let solution = endgame_solver::solve(turn, SolveMode::Exact)?;
+        let best_move = solution.best_move
+            .ok_or(ReversiError::EndedGame(*turn))?;
+        Ok((best_move, solution.score))
+    }
+
+    /// Evaluates each move by an alpha-beta search `depth` plies deep and returns the best one, with its score.
+    /// If time expires during the search, the returned move is meaningless.
+    fn search_root(&self, turn: &turn::Turn, side: Side, moves: &[Coord], depth: u8, context: &SearchContext) -> Result<(Coord, Score)> {
+        // Each move has to be evaluated in order to find the best one
+        let evaluate_move = |&coord: &Coord| {
+            let mut turn_after_move = *turn;
+            turn_after_move
+                .make_move(coord)
+                .expect("The move was checked, but something went wrong!");
+            let score = self.ai_eval(&turn_after_move, depth.saturating_sub(1), context)
+                .expect("Something went wrong with `AlphaBeta::ai_eval`!");
+            (coord, score)
+        };
+        let moves_and_scores = self.map_moves(moves, evaluate_move);
+        let best_move_and_score = match side {
+                Side::Dark => moves_and_scores.iter().min_by_key(|&&(_, score)| score),
+                Side::Light => moves_and_scores.iter().max_by_key(|&&(_, score)| score),
             }
+            .expect("No best move found!");
+        Ok(*best_move_and_score)
+    }
+
+    /// Applies `f` to each move, in parallel unless the search has to be deterministic.
+    fn map_moves<T, F>(&self, moves: &[Coord], f: F) -> Vec<T>
+        where T: Send,
+              F: Fn(&Coord) -> T + Sync + Send
+    {
+        // Threads share the transposition table in unpredictable ways, so deterministic searches are sequential
+        match self.seed {
+            Some(_) => moves.iter().map(f).collect(),
+            None => moves.par_iter().map(f).collect(),
         }
     }
 
-    fn ai_eval(turn: &turn::Turn, comps: u32) -> Result<Score> {
+    fn ai_eval(&self, turn: &turn::Turn, depth: u8, context: &SearchContext) -> Result<Score> {
         if turn.get_state().is_none() {
             Ok(Score::Ended(turn.get_score_diff()))
         } else {
-            let mut score = try!(AiPlayer::ai_eval_with_leftover(turn, comps)).0;
+            let mut score = AlphaBeta::alpha_beta(turn, depth, MIN_SCORE, MAX_SCORE, context)?;
             // Add some randomness
-            let between = Range::new(-RANDOMNESS, RANDOMNESS);
-            let mut rng = thread_rng();
-            score = match score {
-                Score::Running(val) => Score::Running(val * (1.0 + between.ind_sample(&mut rng))),
-                _ => score,
-            };
+            if self.randomness > 0f64 {
+                let between = Range::new(-self.randomness, self.randomness);
+                let jitter = match self.seed {
+                    Some(seed) => between.ind_sample(&mut seeded_rng(seed, turn)),
+                    None => between.ind_sample(&mut thread_rng()),
+                };
+                score = match score {
+                    Score::Running(val) => Score::Running(val * (1.0 + jitter)),
+                    _ => score,
+                };
+            }
             // Done, return
             Ok(score)
         }
     }
 
-    fn ai_eval_with_leftover(turn: &turn::Turn, comps: u32) -> Result<(Score, u32)> {
+    /// Alpha-beta search of the game tree below `turn`, `depth` plies deep.
+    /// Light is the maximizing side and Dark the minimizing one, consistently with `Score`'s ordering.
+    /// The returned score is exact if it lies strictly between `alpha` and `beta`, and a bound otherwise.
+    /// If time expires, the search is abandoned and the returned score is meaningless.
+    fn alpha_beta(turn: &turn::Turn, depth: u8, mut alpha: Score, mut beta: Score, context: &SearchContext) -> Result<Score> {
+        let side = match turn.get_state() {
+            None => return Ok(Score::Ended(turn.get_score_diff())),
+            Some(_) if depth == 0 => return Ok(Score::Running(context.evaluator.evaluate(turn)?)),
+            Some(side) => side,
+        };
 
-        // If everything is alright, turn shouldn't be ended
-        // assert!(!this_turn.is_endgame());
-
-        // Finds all possible legal moves and records their coordinates
-        let mut moves: Vec<Coord>;
-        let mut turn = *turn;
-        loop {
-            moves = Vec::new();
-            for row in 0..board::BOARD_SIZE {
-                for col in 0..board::BOARD_SIZE {
-                    let coord = board::Coord::new(row, col);
-                    if turn.check_move(coord).is_ok() {
-                        moves.push(coord);
-                    }
+        // Look for the results of a previous search of the same position
+        let key = context.table.hash(turn);
+        let mut hash_move = None;
+        if let Some(entry) = context.table.probe(key) {
+            if entry.depth >= depth {
+                match entry.bound {
+                    Bound::Exact => return Ok(entry.score),
+                    Bound::Lower => alpha = cmp::max(alpha, entry.score),
+                    Bound::Upper => beta = cmp::min(beta, entry.score),
                 }
-            }
-            match moves.len() {
-                0 => unreachable!("Endgame should have been detected earlier: here it's a waste of computations!"),
-                1 => {
-                    turn.make_move(moves[0])?; //.expect("There is one move and it should be legit");
-                    if turn.get_state().is_none() {
-                        return Ok((Score::Ended(turn.get_score_diff()), comps));
-                    }
-                }
-                _num_moves => {
-                    break;
-                    // let scores = moves.par_iter().map(|&coord| {
-                    //     let mut turn_after_move = turn;
-                    //     turn_after_move.make_move(coord)
-                    //         .expect("The move was checked, but something went wrong!");
-                    //     match turn_after_move.get_state() {
-                    //         None => Score::Ended(turn_after_move.get_score_diff()),
-                    //         Some(_) if leftover < turns_left => Score::Running(try!(AiPlayer::heavy_eval(&turn_after_move))),
-                    //         _ => {
-                    //             let new_comps = leftover / turns_left; // since leftover >= turns_left, then new_comps >= 1
-                    //             let new_score_leftover = try!(AiPlayer::ai_eval_with_leftover(&turn_after_move, new_comps));
-                    //             leftover += new_score_leftover.1;
-                    //             leftover -= new_comps; // since leftover >= turns_left, leftover - newcomps >= 0
-                    //             new_score_leftover.0
-                    //         }
-                    //     }
-                    // });
-                    // let side = turn.get_state().ok_or_else(|| ReversiError::EndedGame(turn))?;
-                    // return match side {
-                    //     Side::Dark  => scores.min(),
-                    //     Side::Light => scores.max(),
-                    // }.ok_or_else(|| panic!("No best move found!"))
+                if alpha >= beta {
+                    return Ok(entry.score);
                 }
             }
+            hash_move = entry.best_move;
         }
+        let (alpha_orig, beta_orig) = (alpha, beta);
 
-        // If everything is alright, turn shouldn't be ended
-        // assert!(!turn.is_endgame());
+        // The best move of the previous search is tried first, as it is likely to produce a cut-off
+        let mut moves = legal_moves(turn);
+        if let Some(hash_move) = hash_move {
+            if let Some(index) = moves.iter().position(|&coord| coord == hash_move) {
+                moves.swap(0, index);
+            }
+        }
 
-        let mut scores: Vec<Score> = Vec::new();
-        let mut leftover = comps.checked_sub(moves.len() as u32).unwrap_or(0);
+        let mut best = match side {
+            Side::Light => MIN_SCORE,
+            Side::Dark => MAX_SCORE,
+        };
+        let mut best_move = None;
 
-        while let Some(coord) = moves.pop() {
-            let mut turn_after_move = turn;
+        for coord in moves {
+            let mut turn_after_move = *turn;
             turn_after_move.make_move(coord)?;
-            let turns_left = (moves.len() + 1) as u32;
-            scores.push(match turn_after_move.get_state() {
-                            None => Score::Ended(turn_after_move.get_score_diff()),
-                            Some(_) if leftover < turns_left => Score::Running(try!(AiPlayer::heavy_eval(&turn_after_move))),
-                            _ => {
-                                let new_comps = leftover / turns_left; // since leftover >= turns_left, then new_comps >= 1
-                                let new_score_leftover = try!(AiPlayer::ai_eval_with_leftover(&turn_after_move, new_comps));
-                                leftover += new_score_leftover.1;
-                                leftover -= new_comps; // since leftover >= turns_left, leftover - newcomps >= 0
-                                new_score_leftover.0
-                            }
-                        });
+            let score = AlphaBeta::alpha_beta(&turn_after_move, depth - 1, alpha, beta, context)?;
+            if best_move.is_none() ||
+               match side {
+                   Side::Light => score > best,
+                   Side::Dark => score < best,
+               } {
+                best = score;
+                best_move = Some(coord);
+            }
+            match side {
+                Side::Light => alpha = cmp::max(alpha, best),
+                Side::Dark => beta = cmp::min(beta, best),
+            }
+            // Cut-off: the opponent would never let the game reach this position (or there is no time left)
+            if alpha >= beta || context.is_expired() {
+                break;
+            }
         }
 
-        Ok((match turn.get_state() {
-                Some(Side::Dark) => scores.into_iter().min().expect("Why should this fail?"),
-                Some(Side::Light) => scores.into_iter().max().expect("Why should this fail?"),
-                None => unreachable!("turn is ended but it should not be"),
-            },
-            leftover))
-    }
-
-    fn heavy_eval(turn: &turn::Turn) -> Result<f64> {
-        // Weights
-        const CORNER_BONUS: u16 = 50;
-        const ODD_CORNER_MALUS: u16 = 20;
-        const EVEN_CORNER_BONUS: u16 = 10;
-        const ODD_MALUS: u16 = 7; // x2
-        const EVEN_BONUS: u16 = 3; // x2
-        // ------------------------ Sum = 100
-
-        let sides: [(Coord, Coord, Coord, Coord, Coord, Coord, Coord); 4] = [(/* NW corner */
-                                                                              Coord::new(0, 0),
-                                                                              Coord::new(0, 1),
-                                                                              Coord::new(1, 1),
-                                                                              Coord::new(0, 2),
-                                                                              Coord::new(2, 2),
-                                                                              Coord::new(1, 0),
-                                                                              Coord::new(2, 0)),
-                                                                             (/* NE corner */
-                                                                              Coord::new(0, 7),
-                                                                              Coord::new(1, 7),
-                                                                              Coord::new(1, 6),
-                                                                              Coord::new(2, 7),
-                                                                              Coord::new(2, 5),
-                                                                              Coord::new(0, 6),
-                                                                              Coord::new(0, 5)),
-                                                                             (/* SW corner */
-                                                                              Coord::new(7, 0),
-                                                                              Coord::new(6, 0),
-                                                                              Coord::new(6, 1),
-                                                                              Coord::new(5, 0),
-                                                                              Coord::new(5, 2),
-                                                                              Coord::new(7, 1),
-                                                                              Coord::new(7, 2)),
-                                                                             (/* SE corner */
-                                                                              Coord::new(7, 7),
-                                                                              Coord::new(6, 7),
-                                                                              Coord::new(6, 6),
-                                                                              Coord::new(5, 7),
-                                                                              Coord::new(5, 5),
-                                                                              Coord::new(7, 6),
-                                                                              Coord::new(7, 5))];
-
-        let mut score_light: u16 = 0;
-        let mut score_dark: u16 = 0;
-
-        for &(corner, odd, odd_corner, even, even_corner, counter_odd, counter_even) in &sides {
-
-            if let Some(disk) = *turn.get_cell(corner)? {
-                match disk.get_side() {
-                    Side::Light => score_light += CORNER_BONUS,
-                    Side::Dark => score_dark += CORNER_BONUS,
-                }
+        // Results of interrupted searches are unreliable, so they are not stored
+        if !context.is_expired() {
+            let bound = if best <= alpha_orig {
+                Bound::Upper
+            } else if best >= beta_orig {
+                Bound::Lower
             } else {
-                for &(coord_odd, coord_even) in &[(odd, even), (counter_odd, counter_even)] {
-                    if let Some(disk) = *turn.get_cell(coord_odd)? {
-                        match disk.get_side() {
-                            Side::Light => score_dark += ODD_MALUS,
-                            Side::Dark => score_light += ODD_MALUS,
-                        }
-                    } else if let Some(disk) = *turn.get_cell(coord_even)? {
-                        match disk.get_side() {
-                            Side::Light => score_light += EVEN_BONUS,
-                            Side::Dark => score_dark += EVEN_BONUS,
-                        }
-                    }
-                }
-                if let Some(disk) = *turn.get_cell(odd_corner)? {
-                    match disk.get_side() {
-                        Side::Light => score_dark += ODD_CORNER_MALUS,
-                        Side::Dark => score_light += ODD_CORNER_MALUS,
-                    }
+                Bound::Exact
+            };
+            context.table.store(key, Entry::new(depth, bound, best, best_move));
+        }
 
-                } else if let Some(disk) = *turn.get_cell(even_corner)? {
-                    match disk.get_side() {
-                        Side::Light => score_light += EVEN_CORNER_BONUS,
-                        Side::Dark => score_dark += EVEN_CORNER_BONUS,
-                    }
-                }
+        Ok(best)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AlphaBeta, Score, Strength};
+    use evaluation::Evaluator;
+    use reversi::turn::Turn;
+    use std::cmp::Ordering;
+
+    const SCORES: [Score; 7] = [Score::Ended(-2), Score::Running(-1f64), Score::Running(0f64), Score::Ended(0),
+                                Score::Running(1f64), Score::Ended(1), Score::Ended(2)];
+
+    #[test]
+    fn score_order_is_total() {
+        // The scores are listed from the lowest to the highest
+        for (index1, score1) in SCORES.iter().enumerate() {
+            for (index2, score2) in SCORES.iter().enumerate() {
+                assert_eq!(score1.cmp(score2), index1.cmp(&index2));
+                assert_eq!(score1.cmp(score2), score2.cmp(score1).reverse());
             }
         }
-        Ok(score_light as f64 - score_dark as f64)
+    }
+
+    #[test]
+    fn draw_against_zero_evaluation() {
+        assert_eq!(Score::Running(0f64).cmp(&Score::Ended(0)), Ordering::Less);
+        assert_eq!(Score::Ended(0).cmp(&Score::Running(0f64)), Ordering::Greater);
+        assert_eq!(::std::cmp::max(Score::Running(0f64), Score::Ended(0)), Score::Ended(0));
+    }
+
+    #[test]
+    fn table_is_kept_and_inspectable() {
+        let search = AlphaBeta::with_table_size(Strength::Weak, 1 << 10);
+        assert_eq!(search.table().get_size(), 1 << 10);
+        search.best_move_and_score(&Turn::first_turn(), &Evaluator::default()).expect("The game is not over");
+        let probes = search.table().get_hits() + search.table().get_misses();
+        assert!(probes > 0);
+        // Clones share the table
+        search.clone().best_move_and_score(&Turn::first_turn(), &Evaluator::default()).expect("The game is not over");
+        assert!(search.table().get_hits() > 0);
     }
 }
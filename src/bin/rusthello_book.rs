@@ -0,0 +1,68 @@
+//! `rusthello-book`
+//! Builds an opening book for RUSThello's AI players from a corpus of recorded games.
+//! The corpus is a text file with one game per line, in compact move notation (e.g. `f5d6c3d3...`),
+//! or a WTHOR database if its extension is `wtb`.
+//! Games from a text file have to be complete, while WTHOR games are scored by their recorded result,
+//! so that games ended by resignation or on time are learnt too.
+//! Usage: `rusthello-book <corpus file> <book file> [depth]`
+
+extern crate rusthello_lib;
+
+use rusthello_lib::notation;
+use rusthello_lib::opening_book::{OpeningBook, DEFAULT_BOOK_DEPTH};
+use rusthello_lib::wthor::{self, WthorReader};
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process;
+
+const USAGE: &str = "Usage: rusthello-book <corpus file> <book file> [depth]";
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let depth = match args.get(3) {
+        None => DEFAULT_BOOK_DEPTH,
+        Some(depth) => depth.parse().unwrap_or_else(|_| exit_with_error(USAGE)),
+    };
+    if args.len() < 3 || args.len() > 4 {
+        exit_with_error(USAGE);
+    }
+
+    let mut book = OpeningBook::new();
+    let (mut added, mut skipped) = (0, 0);
+    if wthor::is_wthor_file(&args[1]) {
+        let games = WthorReader::open(&args[1]).unwrap_or_else(|err| exit_with_error(&format!("Cannot open {}: {}", args[1], err)));
+        for game in games {
+            match game {
+                Ok(ref game) if book.add_game(&game.moves, Some(game.result()), depth) => added += 1,
+                Ok(_) => skipped += 1,
+                Err(ref err) if err.kind() == io::ErrorKind::InvalidData => skipped += 1,
+                Err(err) => exit_with_error(&format!("Cannot read {}: {}", args[1], err)),
+            }
+        }
+    } else {
+        let corpus = File::open(&args[1]).unwrap_or_else(|err| exit_with_error(&format!("Cannot open {}: {}", args[1], err)));
+        for line in BufReader::new(corpus).lines() {
+            let line = line.unwrap_or_else(|err| exit_with_error(&format!("Cannot read {}: {}", args[1], err)));
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match notation::parse_moves(line) {
+                Some(ref moves) if book.add_game(moves, None, depth) => added += 1,
+                _ => skipped += 1,
+            }
+        }
+    }
+
+    book.save(&args[2]).unwrap_or_else(|err| exit_with_error(&format!("Cannot write {}: {}", args[2], err)));
+    println!("Learned {} positions from {} games ({} skipped).",
+             book.len(),
+             added,
+             skipped);
+}
+
+fn exit_with_error(message: &str) -> ! {
+    let _ = writeln!(io::stderr(), "{}", message);
+    process::exit(1)
+}
@@ -0,0 +1,37 @@
+//! `rusthello-engine`
+//! Runs RUSThello's AI as an engine driven through stdin and stdout, by a protocol compatible with NBoard's
+//! (see the `engine_protocol` module), so that it can be used from Othello GUIs and scripts.
+//! The weights are either a built-in profile or a weights file, and default to the `default` profile.
+//! Usage: `rusthello-engine [weights]`
+
+extern crate rusthello_lib;
+
+use rusthello_lib::engine_protocol;
+use rusthello_lib::evaluation::Evaluator;
+use std::env;
+use std::io::{self, Write};
+use std::process;
+
+const USAGE: &str = "Usage: rusthello-engine [weights]";
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let evaluator = match args.len() {
+        1 => Evaluator::default(),
+        2 => {
+            Evaluator::profile(&args[1])
+                .unwrap_or_else(|| Evaluator::load(&args[1]).unwrap_or_else(|err| exit_with_error(&format!("Cannot read {}: {}", args[1], err))))
+        }
+        _ => exit_with_error(USAGE),
+    };
+
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    engine_protocol::run(evaluator, stdin.lock(), stdout.lock())
+        .unwrap_or_else(|err| exit_with_error(&format!("Engine error: {}", err)));
+}
+
+fn exit_with_error(message: &str) -> ! {
+    let _ = writeln!(io::stderr(), "{}", message);
+    process::exit(1)
+}
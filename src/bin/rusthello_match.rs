@@ -0,0 +1,153 @@
+//! `rusthello-match`
+//! Plays a match between two AI engines, in pairs of games where the engines swap colours,
+//! in parallel unless an engine is timed.
+//! Engines are given as specs such as `medium`, `timed:500@mobility` or `mcts:5000` (see the `engine_match` module).
+//! Pairs start from the positions of a positions file in turn, or from the starting position.
+//! With a seed, the players are deterministic and the match can be replayed.
+//! Prints the wins, losses, draws and disk totals of each engine and the Elo difference between them,
+//! and with `json` or `csv` the records of every game.
+//! With `--sprt <elo0>,<elo1>`, the match is a Sequential Probability Ratio Test of whether the first engine
+//! is `elo1` Elo stronger than the second rather than `elo0` (see the `elo` module):
+//! it stops as soon as either is accepted, or after the given number of pairs.
+//! Usage: `rusthello-match <engine> <engine> [--pairs <pairs>] [--positions <file>] [--seed <seed>] [--sprt <elo0>,<elo1>]
+//! [--format text|json|csv]`
+
+extern crate rusthello_lib;
+extern crate reversi;
+
+use reversi::turn::Turn;
+use rusthello_lib::elo::{self, Sprt, SprtStatus};
+use rusthello_lib::engine_match::{self, EngineConfig};
+use rusthello_lib::notation;
+use std::env;
+use std::io::{self, Write};
+use std::process;
+
+const USAGE: &str = "Usage: rusthello-match <engine> <engine> [--pairs <pairs>] [--positions <file>] [--seed <seed>] \
+                     [--sprt <elo0>,<elo1>] [--format text|json|csv]";
+
+/// Pairs of games played if not given.
+const DEFAULT_PAIRS: usize = 10;
+/// Pairs of games played if not given, for an SPRT.
+const DEFAULT_SPRT_PAIRS: usize = 1000;
+/// Pairs of games played between the checks of an SPRT.
+const SPRT_BATCH_PAIRS: usize = 8;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.len() < 2 {
+        exit_with_error(USAGE);
+    }
+    let engines: Vec<EngineConfig> = args[0..2]
+        .iter()
+        .map(|spec| EngineConfig::parse(spec).unwrap_or_else(|err| exit_with_error(&format!("{}\n{}", err, USAGE))))
+        .collect();
+
+    let (mut pairs, mut starts, mut seed, mut format) = (None, vec![Turn::first_turn()], None, "text".to_string());
+    let mut sprt = None;
+    let mut options = args[2..].iter();
+    while let Some(option) = options.next() {
+        let value = options.next().unwrap_or_else(|| exit_with_error(USAGE));
+        match option.as_str() {
+            "--pairs" => pairs = Some(value.parse().unwrap_or_else(|_| exit_with_error(USAGE))),
+            "--positions" => {
+                starts = engine_match::load_positions(value)
+                    .unwrap_or_else(|err| exit_with_error(&format!("Cannot read {}: {}", value, err)));
+                if starts.is_empty() {
+                    exit_with_error(&format!("No positions in {}", value));
+                }
+            }
+            "--sprt" => {
+                let bounds: Vec<f64> = value.split(',')
+                    .map(|bound| bound.trim().parse().unwrap_or_else(|_| exit_with_error(USAGE)))
+                    .collect();
+                if bounds.len() != 2 || bounds[0] >= bounds[1] {
+                    exit_with_error(USAGE);
+                }
+                sprt = Some(Sprt::new(bounds[0], bounds[1]));
+            }
+            "--seed" => seed = Some(value.parse().unwrap_or_else(|_| exit_with_error(USAGE))),
+            "--format" if ["text", "json", "csv"].contains(&value.as_str()) => format = value.clone(),
+            _ => exit_with_error(USAGE),
+        }
+    }
+
+    let records = match sprt {
+        Some(sprt) => {
+            let pairings = engine_match::paired_schedule(0, 1, pairs.unwrap_or(DEFAULT_SPRT_PAIRS), &starts, seed);
+            engine_match::play_games_until(&engines,
+                                           &pairings,
+                                           2 * SPRT_BATCH_PAIRS,
+                                           |records| sprt.status(&engine_match::summarize(records, 0)) != SprtStatus::Continue)
+        }
+        None => {
+            let pairings = engine_match::paired_schedule(0, 1, pairs.unwrap_or(DEFAULT_PAIRS), &starts, seed);
+            engine_match::play_games(&engines, &pairings)
+        }
+    };
+    let records = records.unwrap_or_else(|err| exit_with_error(&format!("Error during a game: {:?}", err)));
+
+    let stdout = io::stdout();
+    let written = match format.as_str() {
+        "json" => engine_match::write_json(&engines, &records, stdout.lock()),
+        "csv" => engine_match::write_csv(&engines, &records, stdout.lock()),
+        _ => {
+            for record in &records {
+                println!("{} vs {}: {}-{} {}",
+                         engines[record.dark].name,
+                         engines[record.light].name,
+                         record.dark_disks,
+                         record.light_disks,
+                         notation::transcript(&record.start, &record.moves).unwrap_or_default());
+            }
+            Ok(())
+        }
+    };
+    written.unwrap_or_else(|err| exit_with_error(&format!("Cannot write the records: {}", err)));
+
+    // The summary is written apart from the records, so that they can be piped elsewhere
+    if format != "json" {
+        for (index, engine) in engines.iter().enumerate() {
+            let summary = engine_match::summarize(&records, index);
+            let _ = writeln!(io::stderr(),
+                             "{}: {} wins, {} losses, {} draws, {}-{} disks",
+                             engine.name,
+                             summary.wins,
+                             summary.losses,
+                             summary.draws,
+                             summary.disks,
+                             summary.opponent_disks);
+        }
+        if let Some(estimate) = elo::estimate(&engine_match::summarize(&records, 0)) {
+            let _ = writeln!(io::stderr(),
+                             "Elo difference: {:+.1} (95% confidence interval: {:+.1} to {:+.1})",
+                             estimate.elo,
+                             estimate.lower,
+                             estimate.upper);
+        }
+    }
+
+    // The outcome of the SPRT is reported in all formats
+    if let Some(sprt) = sprt {
+        let summary = engine_match::summarize(&records, 0);
+        let (lower, upper) = sprt.bounds();
+        let _ = writeln!(io::stderr(),
+                         "SPRT [{}, {}]: LLR {:.2} ({:.2}, {:.2}) after {} games: {}",
+                         sprt.elo0,
+                         sprt.elo1,
+                         sprt.llr(&summary),
+                         lower,
+                         upper,
+                         summary.games(),
+                         match sprt.status(&summary) {
+                             SprtStatus::AcceptedH0 => "H0 accepted",
+                             SprtStatus::AcceptedH1 => "H1 accepted",
+                             SprtStatus::Continue => "inconclusive",
+                         });
+    }
+}
+
+fn exit_with_error(message: &str) -> ! {
+    let _ = writeln!(io::stderr(), "{}", message);
+    process::exit(1)
+}
@@ -0,0 +1,54 @@
+//! `rusthello-tournament`
+//! Plays a tournament between AI engines and prints its crosstable, with scores and tiebreaks.
+//! Engines are given as specs such as `medium`, `timed:500@mobility` or `mcts:5000` (see the `engine_match` module).
+//! The tournament is a single round robin by default, or a double one with `--cycles 2`, or a Swiss one with `--swiss <rounds>`.
+//! With a seed, the players are deterministic and the tournament can be replayed.
+//! Usage: `rusthello-tournament [--cycles <cycles> | --swiss <rounds>] [--seed <seed>] <engine> <engine>...`
+
+extern crate rusthello_lib;
+
+use rusthello_lib::engine_match::EngineConfig;
+use rusthello_lib::tournament::{Format, Tournament};
+use std::env;
+use std::io::{self, Write};
+use std::process;
+use std::str::FromStr;
+
+const USAGE: &str = "Usage: rusthello-tournament [--cycles <cycles> | --swiss <rounds>] [--seed <seed>] <engine> <engine>...";
+
+fn main() {
+    let (mut format, mut seed, mut engines) = (Format::RoundRobin(1), None, Vec::new());
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--cycles" | "--swiss" | "--seed" => {
+                let value = args.next().unwrap_or_else(|| exit_with_error(USAGE));
+                match arg.as_str() {
+                    "--cycles" => format = Format::RoundRobin(parse_value(&value)),
+                    "--swiss" => format = Format::Swiss(parse_value(&value)),
+                    _ => seed = Some(parse_value(&value)),
+                }
+            }
+            spec => engines.push(EngineConfig::parse(spec).unwrap_or_else(|err| exit_with_error(&format!("{}\n{}", err, USAGE)))),
+        }
+    }
+    if engines.len() < 2 {
+        exit_with_error(USAGE);
+    }
+
+    let mut tournament = Tournament::new(engines);
+    tournament.play(format, seed).unwrap_or_else(|err| exit_with_error(&format!("Error during a game: {:?}", err)));
+    let stdout = io::stdout();
+    tournament.write_crosstable(stdout.lock())
+        .unwrap_or_else(|err| exit_with_error(&format!("Cannot write the crosstable: {}", err)));
+}
+
+/// Parses the value of an option, exiting with the usage if it is not valid.
+fn parse_value<T: FromStr>(value: &str) -> T {
+    value.parse().unwrap_or_else(|_| exit_with_error(USAGE))
+}
+
+fn exit_with_error(message: &str) -> ! {
+    let _ = writeln!(io::stderr(), "{}", message);
+    process::exit(1)
+}
@@ -0,0 +1,63 @@
+//! `rusthello-tune`
+//! Tunes the evaluation weights of RUSThello's AI players on a file of labelled positions,
+//! one per line in the format `<moves> <result>` (e.g. `f5d6c3d3c4 -6`, see the `tuning` module),
+//! or on every position of the games of a WTHOR database if its extension is `wtb`.
+//! The initial weights are either a built-in profile or a weights file, and default to the `default` profile.
+//! Usage: `rusthello-tune <positions file> <weights file> [initial weights] [iterations]`
+
+extern crate rusthello_lib;
+
+use rusthello_lib::evaluation::Evaluator;
+use rusthello_lib::tuning::{self, DEFAULT_ITERATIONS};
+use rusthello_lib::wthor;
+use std::env;
+use std::io::{self, Write};
+use std::process;
+
+const USAGE: &str = "Usage: rusthello-tune <positions file> <weights file> [initial weights] [iterations]";
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 3 || args.len() > 5 {
+        exit_with_error(USAGE);
+    }
+    let evaluator = match args.get(3) {
+        None => Evaluator::default(),
+        Some(name) => {
+            Evaluator::profile(name)
+                .unwrap_or_else(|| Evaluator::load(name).unwrap_or_else(|err| exit_with_error(&format!("Cannot read {}: {}", name, err))))
+        }
+    };
+    let iterations = match args.get(4) {
+        None => DEFAULT_ITERATIONS,
+        Some(iterations) => iterations.parse().unwrap_or_else(|_| exit_with_error(USAGE)),
+    };
+
+    let positions = if wthor::is_wthor_file(&args[1]) {
+        wthor::load_games(&args[1])
+            .map(|games| {
+                     games.iter()
+                         .filter_map(|game| tuning::label_game(&game.moves, game.result()))
+                         .flatten()
+                         .collect()
+                 })
+    } else {
+        tuning::load_positions(&args[1])
+    };
+    let positions = positions.unwrap_or_else(|err| exit_with_error(&format!("Cannot read {}: {}", args[1], err)));
+    if positions.is_empty() {
+        exit_with_error(&format!("No positions to tune on in {}", args[1]));
+    }
+
+    let (tuned, initial_error, final_error) = tuning::tune(&evaluator, &positions, iterations);
+    tuned.save(&args[2]).unwrap_or_else(|err| exit_with_error(&format!("Cannot write {}: {}", args[2], err)));
+    println!("Tuned on {} positions: error went from {:.6} to {:.6}.",
+             positions.len(),
+             initial_error,
+             final_error);
+}
+
+fn exit_with_error(message: &str) -> ! {
+    let _ = writeln!(io::stderr(), "{}", message);
+    process::exit(1)
+}
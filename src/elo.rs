@@ -0,0 +1,185 @@
+//! Provides Elo rating estimates and the Sequential Probability Ratio Test (SPRT) on the results of a match,
+//! to tell whether a change to an engine makes it stronger without playing a fixed, large number of games.
+//!
+//! An Elo difference `d` means an expected score of `1 / (1 + 10^(-d / 400))` per game.
+//! The SPRT weighs hypothesis H0 (the difference is `elo0`) against H1 (the difference is `elo1`)
+//! after every batch of games, using the normal approximation of the log-likelihood ratio (LLR) of the results,
+//! and stops as soon as the LLR crosses either of the bounds given by the error rates.
+
+use engine_match::Summary;
+
+/// The z-score of 95% confidence intervals.
+const Z_95: f64 = 1.959964f64;
+
+/// Default probability of accepting H1 when H0 holds, and of accepting H0 when H1 holds.
+pub const DEFAULT_ERROR_RATE: f64 = 0.05f64;
+
+/// An Elo difference with its confidence interval.
+#[derive(Debug, Clone, Copy)]
+pub struct EloEstimate {
+    pub elo: f64,
+    /// The bounds of the 95% confidence interval.
+    pub lower: f64,
+    pub upper: f64,
+}
+
+/// The Elo difference corresponding to an expected score `score`, which is infinite for scores of 0 and 1.
+pub fn score_to_elo(score: f64) -> f64 {
+    -400f64 * (1f64 / score - 1f64).log10()
+}
+
+/// The expected score corresponding to the Elo difference `elo`.
+pub fn elo_to_score(elo: f64) -> f64 {
+    1f64 / (1f64 + 10f64.powf(-elo / 400f64))
+}
+
+/// The mean and the variance of the score of a single game in the results of `summary`.
+fn score_stats(summary: &Summary) -> (f64, f64) {
+    let games = summary.games() as f64;
+    let score = summary.points() / games;
+    let variance = (summary.wins as f64 * (1f64 - score).powi(2) + summary.draws as f64 * (0.5f64 - score).powi(2) +
+                    summary.losses as f64 * score.powi(2)) / games;
+    (score, variance)
+}
+
+/// Estimates the Elo difference between an engine and its opponents from its results.
+/// Returns `None` if it won or lost every game (draws included), as the difference is then unbounded.
+pub fn estimate(summary: &Summary) -> Option<EloEstimate> {
+    if summary.games() == 0 || summary.wins + summary.draws == 0 || summary.losses + summary.draws == 0 {
+        return None;
+    }
+    let (score, variance) = score_stats(summary);
+    let margin = Z_95 * (variance / summary.games() as f64).sqrt();
+    Some(EloEstimate {
+             elo: score_to_elo(score),
+             lower: score_to_elo((score - margin).max(0f64)),
+             upper: score_to_elo((score + margin).min(1f64)),
+         })
+}
+
+/// The outcome of an SPRT so far.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SprtStatus {
+    /// The Elo difference is `elo0` or less: the change is no improvement.
+    AcceptedH0,
+    /// The Elo difference is `elo1` or more: the change is an improvement.
+    AcceptedH1,
+    /// More games are needed.
+    Continue,
+}
+
+/// A Sequential Probability Ratio Test of H0: "the Elo difference is `elo0`" against H1: "it is `elo1`".
+#[derive(Debug, Clone, Copy)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    /// Probability of accepting H1 when H0 holds.
+    pub alpha: f64,
+    /// Probability of accepting H0 when H1 holds.
+    pub beta: f64,
+}
+
+impl Sprt {
+    /// Creates a test between `elo0` and `elo1`, with the default error rates.
+    pub fn new(elo0: f64, elo1: f64) -> Sprt {
+        Sprt {
+            elo0,
+            elo1,
+            alpha: DEFAULT_ERROR_RATE,
+            beta: DEFAULT_ERROR_RATE,
+        }
+    }
+
+    /// The bounds of the LLR: H0 is accepted below the lower one and H1 above the upper one.
+    pub fn bounds(&self) -> (f64, f64) {
+        ((self.beta / (1f64 - self.alpha)).ln(), ((1f64 - self.beta) / self.alpha).ln())
+    }
+
+    /// The log-likelihood ratio of H1 against H0 given the results in `summary`.
+    /// It is 0 until the results vary, since their variance cannot be estimated before.
+    pub fn llr(&self, summary: &Summary) -> f64 {
+        if summary.games() == 0 {
+            return 0f64;
+        }
+        let (score, variance) = score_stats(summary);
+        if variance == 0f64 {
+            return 0f64;
+        }
+        let (score0, score1) = (elo_to_score(self.elo0), elo_to_score(self.elo1));
+        summary.games() as f64 * (score1 - score0) * (2f64 * score - score0 - score1) / (2f64 * variance)
+    }
+
+    /// Whether the test has reached a decision given the results in `summary`.
+    pub fn status(&self, summary: &Summary) -> SprtStatus {
+        let llr = self.llr(summary);
+        let (lower, upper) = self.bounds();
+        if llr >= upper {
+            SprtStatus::AcceptedH1
+        } else if llr <= lower {
+            SprtStatus::AcceptedH0
+        } else {
+            SprtStatus::Continue
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{elo_to_score, estimate, score_to_elo, Sprt, SprtStatus};
+    use engine_match::Summary;
+
+    fn summary(wins: u32, losses: u32, draws: u32) -> Summary {
+        Summary {
+            wins,
+            losses,
+            draws,
+            disks: 0,
+            opponent_disks: 0,
+        }
+    }
+
+    #[test]
+    fn convert_scores_and_elo() {
+        assert_eq!(score_to_elo(0.5f64), 0f64);
+        assert_eq!(elo_to_score(0f64), 0.5f64);
+        assert!(score_to_elo(0.75f64) > 0f64);
+        assert!(score_to_elo(0.25f64) < 0f64);
+        for &elo in &[-800f64, -200f64, -5f64, 1f64, 35.5f64, 400f64] {
+            assert!((score_to_elo(elo_to_score(elo)) - elo).abs() < 1e-9, "{} is not recovered", elo);
+        }
+    }
+
+    #[test]
+    fn estimate_elo() {
+        assert!(estimate(&summary(0, 0, 0)).is_none());
+        assert!(estimate(&summary(10, 0, 0)).is_none());
+        assert!(estimate(&summary(0, 10, 0)).is_none());
+
+        let winning = estimate(&summary(30, 20, 10)).expect("The difference is bounded");
+        assert!((winning.elo - score_to_elo(35f64 / 60f64)).abs() < 1e-9);
+        assert!(winning.lower < winning.elo && winning.elo < winning.upper);
+        assert!(winning.elo > 0f64);
+        let losing = estimate(&summary(20, 30, 10)).expect("The difference is bounded");
+        assert!((losing.elo + winning.elo).abs() < 1e-9);
+        assert!(losing.lower < losing.elo && losing.elo < losing.upper);
+    }
+
+    #[test]
+    fn sprt_decisions() {
+        let sprt = Sprt::new(0f64, 10f64);
+        let (lower, upper) = sprt.bounds();
+        assert!(lower < 0f64 && upper > 0f64);
+        assert_eq!(sprt.status(&summary(900, 100, 0)), SprtStatus::AcceptedH1);
+        assert_eq!(sprt.status(&summary(100, 900, 0)), SprtStatus::AcceptedH0);
+        assert_eq!(sprt.status(&summary(6, 5, 2)), SprtStatus::Continue);
+    }
+
+    #[test]
+    fn sprt_needs_varying_results() {
+        let sprt = Sprt::new(0f64, 10f64);
+        for results in &[summary(0, 0, 0), summary(10, 0, 0), summary(0, 10, 0), summary(0, 0, 10)] {
+            assert_eq!(sprt.llr(results), 0f64);
+            assert_eq!(sprt.status(results), SprtStatus::Continue);
+        }
+    }
+}
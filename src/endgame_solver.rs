@@ -0,0 +1,232 @@
+//! Provides an exact solver for endgame positions.
+//! Instead of relying on heuristics, it searches the game tree down to the end of the game,
+//! so the scores it returns are proven final disk differentials.
+
+use ai_player::{self, Score};
+use rayon::prelude::*;
+use reversi::{turn, Side, ReversiError};
+use reversi::board::{BOARD_SIZE, Coord};
+use std::cmp;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use Result;
+
+/// At or below this number of empty cells, strong and timed `AlphaBeta` searches switch to the exact solver,
+/// unless set otherwise with `AlphaBeta::with_endgame_empty_cells`.
+/// Solving takes about 0.2 seconds at 12 empty cells, 1 second at 14, 6 to 16 seconds at 16
+/// and more than a minute at 18 (measured in release builds on midgame positions),
+/// as every node copies and checks a whole `reversi::turn::Turn` rather than working on bitboards.
+/// Switching at around 20 empty cells would make strong players stall for minutes near the end of the game,
+/// so the default stays at 12. Timed searches never stall, as the solver gives up when it runs out of time.
+pub const ENDGAME_EMPTY_CELLS: usize = 12;
+
+/// Above this number of empty cells, moves are ordered fastest-first (see `ordered_moves`),
+/// which costs a mobility count for each move but saves far more nodes than it costs.
+const FASTEST_FIRST_EMPTY_CELLS: usize = 6;
+
+/// Disk differentials lie in this range, so it contains every possible outcome.
+const MIN_DIFF: i16 = -64;
+const MAX_DIFF: i16 = 64;
+
+/// Static priority of each cell when ordering moves: corners first, cells next to corners last.
+const CELL_PRIORITY: [[u8; 8]; 8] = [[9, 2, 7, 6, 6, 7, 2, 9],
+                                     [2, 1, 3, 4, 4, 3, 1, 2],
+                                     [7, 3, 5, 5, 5, 5, 3, 7],
+                                     [6, 4, 5, 0, 0, 5, 4, 6],
+                                     [6, 4, 5, 0, 0, 5, 4, 6],
+                                     [7, 3, 5, 5, 5, 5, 3, 7],
+                                     [2, 1, 3, 4, 4, 3, 1, 2],
+                                     [9, 2, 7, 6, 6, 7, 2, 9]];
+
+/// What the solver has to prove.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SolveMode {
+    /// Only proves whether the game is won, lost or drawn, which is much faster.
+    /// The resulting score is `Score::Ended(1)` if Light wins, `Score::Ended(-1)` if Dark wins and `Score::Ended(0)` for a draw.
+    WinLossDraw,
+    /// Proves the exact final disk differential.
+    Exact,
+}
+
+/// The outcome of solving a position under perfect play.
+#[derive(Debug, Clone, Copy)]
+pub struct Solution {
+    /// The final score (as Light's disks minus Dark's disks), always a `Score::Ended`.
+    pub score: Score,
+    /// The move achieving `score`, or `None` if the game is already ended.
+    pub best_move: Option<Coord>,
+}
+
+/// Time limit of a solver run, shared by all the threads working on it.
+struct Limit {
+    deadline: Option<Instant>,
+    expired: AtomicBool,
+}
+
+impl Limit {
+    /// Checks whether the solver has run out of time, once and for all.
+    fn is_expired(&self) -> bool {
+        if self.expired.load(Ordering::Relaxed) {
+            true
+        } else if self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            self.expired.store(true, Ordering::Relaxed);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Solves the position of `turn` by searching the whole game tree below it.
+/// Beware that the time needed grows exponentially with the number of empty cells.
+pub fn solve(turn: &turn::Turn, mode: SolveMode) -> Result<Solution> {
+    let limit = Limit {
+        deadline: None,
+        expired: AtomicBool::new(false),
+    };
+    solve_within(turn, mode, &limit).map(|solution| solution.expect("Solving without a time limit always completes"))
+}
+
+/// Solves the position of `turn` like `solve`, but gives up once `time` runs out, returning `None`.
+pub fn solve_in_time(turn: &turn::Turn, mode: SolveMode, time: Duration) -> Result<Option<Solution>> {
+    let limit = Limit {
+        deadline: Some(Instant::now() + time),
+        expired: AtomicBool::new(false),
+    };
+    solve_within(turn, mode, &limit)
+}
+
+fn solve_within(turn: &turn::Turn, mode: SolveMode, limit: &Limit) -> Result<Option<Solution>> {
+    let side = match turn.get_state() {
+        None => {
+            return Ok(Some(Solution {
+                               score: Score::Ended(turn.get_score_diff()),
+                               best_move: None,
+                           }))
+        }
+        Some(side) => side,
+    };
+
+    let (alpha, beta) = match mode {
+        SolveMode::WinLossDraw => (-1, 1),
+        SolveMode::Exact => (MIN_DIFF, MAX_DIFF),
+    };
+
+    // The root moves are solved in parallel
+    let moves_and_diffs = ordered_moves(turn)
+        .par_iter()
+        .map(|&coord| {
+            let mut turn_after_move = *turn;
+            turn_after_move
+                .make_move(coord)
+                .expect("The move was checked, but something went wrong!");
+            let diff = solve_diff(&turn_after_move, alpha, beta, limit).expect("Something went wrong with `solve_diff`!");
+            (coord, diff)
+        })
+        .collect::<Vec<(Coord, i16)>>();
+
+    // The results of an interrupted search are unreliable
+    if limit.is_expired() {
+        return Ok(None);
+    }
+
+    let &(best_move, best_diff) = match side {
+            Side::Light => moves_and_diffs.iter().max_by_key(|&&(_, diff)| diff),
+            Side::Dark => moves_and_diffs.iter().min_by_key(|&&(_, diff)| diff),
+        }
+        .ok_or(ReversiError::EndedGame(*turn))?;
+
+    Ok(Some(Solution {
+                score: Score::Ended(match mode {
+                                        SolveMode::WinLossDraw => best_diff.signum(),
+                                        SolveMode::Exact => best_diff,
+                                    }),
+                best_move: Some(best_move),
+            }))
+}
+
+/// Alpha-beta search of the whole game tree below `turn`, on final disk differentials.
+/// Light is the maximizing side and Dark the minimizing one.
+/// If time expires, the search is abandoned and the returned differential is meaningless.
+fn solve_diff(turn: &turn::Turn, mut alpha: i16, mut beta: i16, limit: &Limit) -> Result<i16> {
+    let side = match turn.get_state() {
+        None => return Ok(turn.get_score_diff()),
+        Some(side) => side,
+    };
+
+    let mut best = match side {
+        Side::Light => MIN_DIFF,
+        Side::Dark => MAX_DIFF,
+    };
+
+    for coord in ordered_moves(turn) {
+        let mut turn_after_move = *turn;
+        turn_after_move.make_move(coord)?;
+        let diff = solve_diff(&turn_after_move, alpha, beta, limit)?;
+        match side {
+            Side::Light => {
+                best = cmp::max(best, diff);
+                alpha = cmp::max(alpha, best);
+            }
+            Side::Dark => {
+                best = cmp::min(best, diff);
+                beta = cmp::min(beta, best);
+            }
+        }
+        if alpha >= beta || limit.is_expired() {
+            break;
+        }
+    }
+
+    Ok(best)
+}
+
+/// What moves are sorted by in `ordered_moves`: the replies left to the opponent, whether the move's region
+/// has an even number of empty cells, and the priority of the move's cell.
+type MoveKey = (usize, bool, cmp::Reverse<u8>);
+
+/// Legal moves of `turn`, sorted so that the most promising are tried first.
+/// Far from the end, moves leaving the opponent fewer replies come first (fastest-first ordering),
+/// as they lead to smaller subtrees and are often the best ones.
+/// Then moves in regions with an odd number of empty cells come first (parity ordering),
+/// so that the side to move is likely to play the last move of each region, and finally the best placed cells.
+fn ordered_moves(turn: &turn::Turn) -> Vec<Coord> {
+    let empty_cells = ai_player::empty_cells(turn);
+
+    // The board is split in quadrants, whose empty cells are counted for parity
+    let mut region_empty_cells = [0usize; 4];
+    for row in 0..BOARD_SIZE {
+        for col in 0..BOARD_SIZE {
+            if turn.get_cell(Coord::new(row, col)).expect("Coordinates are within the board").is_none() {
+                region_empty_cells[region(row, col)] += 1;
+            }
+        }
+    }
+
+    let mut keyed_moves: Vec<(MoveKey, Coord)> = ai_player::legal_moves(turn)
+        .into_iter()
+        .map(|coord| {
+            let replies = if empty_cells > FASTEST_FIRST_EMPTY_CELLS {
+                let mut turn_after_move = *turn;
+                turn_after_move.make_move(coord).expect("The move is legal");
+                // The opponent has no reply if it has to pass, or if the game ends
+                match turn_after_move.get_state() {
+                    Some(side) if Some(side) != turn.get_state() => ai_player::legal_moves(&turn_after_move).len(),
+                    _ => 0,
+                }
+            } else {
+                0
+            };
+            let even_region = region_empty_cells[region(coord.get_row(), coord.get_col())] % 2 == 0;
+            let priority = CELL_PRIORITY[coord.get_row()][coord.get_col()];
+            ((replies, even_region, cmp::Reverse(priority)), coord)
+        })
+        .collect();
+    keyed_moves.sort_by_key(|&(key, _)| key);
+    keyed_moves.into_iter().map(|(_, coord)| coord).collect()
+}
+
+/// The quadrant of the board the cell is in.
+fn region(row: usize, col: usize) -> usize {
+    2 * (row / (BOARD_SIZE / 2)) + col / (BOARD_SIZE / 2)
+}
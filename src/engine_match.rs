@@ -0,0 +1,471 @@
+//! Provides headless matches between AI engines, played in parallel unless an engine is timed,
+//! with their records in JSON or CSV.
+//!
+//! Engines are configured by specs in the format `<engine>[@<weights>]`, where the engine is one of
+//! `weak`, `medium`, `strong`, `timed:<milliseconds per move>`, `mcts` or `mcts:<playouts>`,
+//! and the weights, which only alpha-beta engines take, are either a built-in profile or a weights file
+//! (see the `evaluation` module), e.g. `medium@mobility` or `timed:500@tuned.toml`.
+//!
+//! A positions file lists the starting positions of a match, one per line,
+//! either as a position string or as a sequence of moves from the starting position (see the `notation` module).
+//! Empty lines and lines starting with `#` are ignored.
+
+use {Result, OtherAction};
+use ai_player::{AiPlayer, AlphaBeta, Strength, RANDOMNESS};
+use elo;
+use evaluation::Evaluator;
+use mcts_player::{self, Budget, MctsPlayer, PlayoutPolicy};
+use notation;
+use rayon::prelude::*;
+use reversi::game::IsPlayer;
+use reversi::board::Coord;
+use reversi::turn::Turn;
+use setup_game::SetupGame;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::time::Duration;
+
+/// The kinds of AI engines.
+#[derive(Debug, Clone, Copy)]
+pub enum EngineKind {
+    AlphaBeta(Strength),
+    Mcts(Budget),
+}
+
+/// An AI engine with its evaluation weights.
+#[derive(Debug, Clone)]
+pub struct EngineConfig {
+    /// The spec the engine was configured by, which names it in records.
+    pub name: String,
+    pub kind: EngineKind,
+    pub evaluator: Evaluator,
+}
+
+impl EngineConfig {
+    /// Configures an engine by its spec, loading its weights file if it has one.
+    pub fn parse(spec: &str) -> io::Result<EngineConfig> {
+        let mut parts = spec.splitn(2, '@');
+        let engine = parts.next().unwrap_or("");
+        let mut fields = engine.splitn(2, ':');
+        let kind = match (fields.next(), fields.next().map(|field| field.parse::<u32>())) {
+            (Some("weak"), None) => EngineKind::AlphaBeta(Strength::Weak),
+            (Some("medium"), None) => EngineKind::AlphaBeta(Strength::Medium),
+            (Some("strong"), None) => EngineKind::AlphaBeta(Strength::Strong),
+            (Some("timed"), Some(Ok(millis))) => EngineKind::AlphaBeta(Strength::Timed(Duration::from_millis(millis as u64))),
+            (Some("mcts"), None) => EngineKind::Mcts(Budget::Playouts(mcts_player::DEFAULT_PLAYOUTS)),
+            (Some("mcts"), Some(Ok(playouts))) => EngineKind::Mcts(Budget::Playouts(playouts)),
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid engine: {}", spec))),
+        };
+        let evaluator = match (kind, parts.next()) {
+            (_, None) => Evaluator::default(),
+            // MCTS engines play random playouts and never evaluate positions
+            (EngineKind::Mcts(_), Some(_)) => {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("MCTS engines take no weights: {}", spec)))
+            }
+            (EngineKind::AlphaBeta(_), Some(weights)) => {
+                match Evaluator::profile(weights) {
+                    Some(profile) => profile,
+                    None => Evaluator::load(weights)?,
+                }
+            }
+        };
+        Ok(EngineConfig {
+               name: spec.to_string(),
+               kind,
+               evaluator,
+           })
+    }
+
+    /// Whether the engine moves within a time limit rather than after a fixed amount of work.
+    pub fn is_timed(&self) -> bool {
+        matches!(self.kind, EngineKind::AlphaBeta(Strength::Timed(_)) | EngineKind::Mcts(Budget::Time(_)))
+    }
+
+    /// Creates a player for the engine, which is deterministic if `seed` is given.
+    pub fn player(&self, seed: Option<u64>) -> Box<IsPlayer<OtherAction>> {
+        match (self.kind, seed) {
+            (EngineKind::AlphaBeta(strength), Some(seed)) => {
+                Box::new(AiPlayer::compose(AlphaBeta::seeded(strength, seed, RANDOMNESS), self.evaluator))
+            }
+            (EngineKind::AlphaBeta(strength), None) => Box::new(AiPlayer::with_evaluator(strength, self.evaluator)),
+            (EngineKind::Mcts(budget), Some(seed)) => Box::new(MctsPlayer::seeded(budget, PlayoutPolicy::CornersFirst, seed)),
+            (EngineKind::Mcts(budget), None) => Box::new(MctsPlayer::new(budget, PlayoutPolicy::CornersFirst)),
+        }
+    }
+}
+
+/// A game to be played, between engines given by their index.
+#[derive(Debug, Clone, Copy)]
+pub struct Pairing {
+    pub dark: usize,
+    pub light: usize,
+    pub start: Turn,
+    /// If set, the game is played by deterministic players seeded with it.
+    pub seed: Option<u64>,
+}
+
+/// The record of a game played.
+#[derive(Debug, Clone)]
+pub struct GameRecord {
+    /// The engines which played the game, by index.
+    pub dark: usize,
+    pub light: usize,
+    pub start: Turn,
+    pub moves: Vec<Coord>,
+    pub dark_disks: u8,
+    pub light_disks: u8,
+}
+
+impl GameRecord {
+    /// The final disk differential (Light's disks minus Dark's disks).
+    pub fn result(&self) -> i16 {
+        self.light_disks as i16 - self.dark_disks as i16
+    }
+
+    /// The disks of `engine` and of its opponent at the end of the game, or `None` if it did not play it.
+    pub fn disks_of(&self, engine: usize) -> Option<(u8, u8)> {
+        if engine == self.dark {
+            Some((self.dark_disks, self.light_disks))
+        } else if engine == self.light {
+            Some((self.light_disks, self.dark_disks))
+        } else {
+            None
+        }
+    }
+}
+
+/// The results of an engine over a set of games.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Summary {
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+    /// The engine's disks at the end of its games, and its opponents' ones.
+    pub disks: u32,
+    pub opponent_disks: u32,
+}
+
+impl Summary {
+    /// Points scored, counting draws as half a win.
+    pub fn points(&self) -> f64 {
+        self.wins as f64 + self.draws as f64 / 2f64
+    }
+
+    pub fn games(&self) -> u32 {
+        self.wins + self.losses + self.draws
+    }
+}
+
+/// Sums up the results of `engine` in `records`.
+pub fn summarize(records: &[GameRecord], engine: usize) -> Summary {
+    let mut summary = Summary::default();
+    for (disks, opponent_disks) in records.iter().filter_map(|record| record.disks_of(engine)) {
+        if disks > opponent_disks {
+            summary.wins += 1;
+        } else if disks < opponent_disks {
+            summary.losses += 1;
+        } else {
+            summary.draws += 1;
+        }
+        summary.disks += disks as u32;
+        summary.opponent_disks += opponent_disks as u32;
+    }
+    summary
+}
+
+/// Schedules `pairs` pairs of games between engines `first` and `second`, swapping colours within each pair.
+/// Pairs start from each of `starts` in turn, and are seeded from `seed` if it is given.
+pub fn paired_schedule(first: usize, second: usize, pairs: usize, starts: &[Turn], seed: Option<u64>) -> Vec<Pairing> {
+    let mut pairings = Vec::with_capacity(2 * pairs);
+    for pair in 0..pairs {
+        let start = starts[pair % starts.len()];
+        for &(dark, light) in &[(first, second), (second, first)] {
+            pairings.push(Pairing {
+                              dark,
+                              light,
+                              start,
+                              seed: seed.map(|seed| seed.wrapping_add(pairings.len() as u64)),
+                          });
+        }
+    }
+    pairings
+}
+
+/// Plays a game between two engines, as given by `pairing`.
+pub fn play_game(engines: &[EngineConfig], pairing: &Pairing) -> Result<GameRecord> {
+    let dark = engines[pairing.dark].player(pairing.seed);
+    let light = engines[pairing.light].player(pairing.seed);
+    let mut game = SetupGame::new(&*dark, &*light, pairing.start);
+    while !game.is_endgame() {
+        game.play_turn()?;
+    }
+    let (dark_disks, light_disks) = game.get_current_turn().get_score();
+    Ok(GameRecord {
+           dark: pairing.dark,
+           light: pairing.light,
+           start: pairing.start,
+           moves: game.get_moves().to_vec(),
+           dark_disks,
+           light_disks,
+       })
+}
+
+/// Plays all the games of `pairings` in parallel, returning their records in the same order.
+/// Games with a timed engine are played one after the other instead: a timed engine searches as far as its time allows,
+/// so playing them alongside other games would make the results depend on the number of cores.
+pub fn play_games(engines: &[EngineConfig], pairings: &[Pairing]) -> Result<Vec<GameRecord>> {
+    let timed = |pairing: &Pairing| engines[pairing.dark].is_timed() || engines[pairing.light].is_timed();
+    if pairings.iter().any(&timed) {
+        pairings.iter().map(|pairing| play_game(engines, pairing)).collect()
+    } else {
+        pairings.par_iter().map(|pairing| play_game(engines, pairing)).collect()
+    }
+}
+
+/// Plays the games of `pairings` in batches of `batch` games (see `play_games`), in order,
+/// stopping after the first batch for which `stop` holds on the records of the games played so far.
+pub fn play_games_until<F>(engines: &[EngineConfig], pairings: &[Pairing], batch: usize, mut stop: F) -> Result<Vec<GameRecord>>
+    where F: FnMut(&[GameRecord]) -> bool
+{
+    let mut records = Vec::with_capacity(pairings.len());
+    for chunk in pairings.chunks(batch.max(1)) {
+        records.extend(play_games(engines, chunk)?);
+        if stop(&records) {
+            break;
+        }
+    }
+    Ok(records)
+}
+
+/// Loads starting positions from the file at `path`.
+pub fn load_positions<P: AsRef<Path>>(path: P) -> io::Result<Vec<Turn>> {
+    read_positions(BufReader::new(File::open(path)?))
+}
+
+/// Reads starting positions in the positions file format.
+pub fn read_positions<R: BufRead>(reader: R) -> io::Result<Vec<Turn>> {
+    let mut positions = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let position = notation::parse_position(line)
+            .or_else(|| notation::parse_moves(line).and_then(|moves| notation::replay(&Turn::first_turn(), &moves)));
+        match position {
+            Some(turn) if turn.get_state().is_some() => positions.push(turn),
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Invalid position line: {}", line))),
+        }
+    }
+    Ok(positions)
+}
+
+/// Writes the summary of each engine and the records of the games in JSON.
+/// The summary includes the Elo difference between the engine and its opponents, with its 95% confidence interval,
+/// which is `null` if it won or lost every game.
+pub fn write_json<W: Write>(engines: &[EngineConfig], records: &[GameRecord], mut writer: W) -> io::Result<()> {
+    writeln!(writer, "{{")?;
+    writeln!(writer, "  \"engines\": [")?;
+    for (index, engine) in engines.iter().enumerate() {
+        let summary = summarize(records, index);
+        let elo = match elo::estimate(&summary) {
+            Some(estimate) => format!("{{\"elo\": {:.1}, \"lower\": {:.1}, \"upper\": {:.1}}}", estimate.elo, estimate.lower, estimate.upper),
+            None => "null".to_string(),
+        };
+        writeln!(writer,
+                 "    {{\"name\": {}, \"wins\": {}, \"losses\": {}, \"draws\": {}, \"disks\": {}, \"opponent_disks\": {}, \"elo\": {}}}{}",
+                 json_string(&engine.name),
+                 summary.wins,
+                 summary.losses,
+                 summary.draws,
+                 summary.disks,
+                 summary.opponent_disks,
+                 elo,
+                 if index + 1 < engines.len() { "," } else { "" })?;
+    }
+    writeln!(writer, "  ],")?;
+    writeln!(writer, "  \"games\": [")?;
+    for (index, record) in records.iter().enumerate() {
+        writeln!(writer,
+                 "    {{\"dark\": {}, \"light\": {}, \"start\": \"{}\", \"moves\": \"{}\", \"dark_disks\": {}, \"light_disks\": {}}}{}",
+                 json_string(&engines[record.dark].name),
+                 json_string(&engines[record.light].name),
+                 notation::position_to_string(&record.start),
+                 transcript(record),
+                 record.dark_disks,
+                 record.light_disks,
+                 if index + 1 < records.len() { "," } else { "" })?;
+    }
+    writeln!(writer, "  ]")?;
+    writeln!(writer, "}}")?;
+    writer.flush()
+}
+
+/// Writes the records of the games in CSV, one per line after a header line.
+pub fn write_csv<W: Write>(engines: &[EngineConfig], records: &[GameRecord], mut writer: W) -> io::Result<()> {
+    writeln!(writer, "dark,light,start,moves,dark_disks,light_disks")?;
+    for record in records {
+        writeln!(writer,
+                 "{},{},{},{},{},{}",
+                 csv_field(&engines[record.dark].name),
+                 csv_field(&engines[record.light].name),
+                 notation::position_to_string(&record.start),
+                 transcript(record),
+                 record.dark_disks,
+                 record.light_disks)?;
+    }
+    writer.flush()
+}
+
+/// The moves of a game in compact notation, with the passes.
+fn transcript(record: &GameRecord) -> String {
+    notation::transcript(&record.start, &record.moves).expect("The moves were played in the game")
+}
+
+/// Quotes `string` as a JSON string.
+fn json_string(string: &str) -> String {
+    let mut quoted = String::with_capacity(string.len() + 2);
+    quoted.push('"');
+    for c in string.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Quotes `string` as a CSV field if it needs to.
+fn csv_field(string: &str) -> String {
+    if string.contains(',') || string.contains('"') || string.contains('\n') {
+        format!("\"{}\"", string.replace('"', "\"\""))
+    } else {
+        string.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{csv_field, json_string, paired_schedule, read_positions, summarize, write_csv, write_json, EngineConfig,
+                GameRecord};
+    use notation;
+    use reversi::turn::Turn;
+
+    fn record(dark: usize, light: usize, dark_disks: u8, light_disks: u8) -> GameRecord {
+        GameRecord {
+            dark,
+            light,
+            start: Turn::first_turn(),
+            moves: notation::parse_moves("f5d6c3").expect("The moves are valid"),
+            dark_disks,
+            light_disks,
+        }
+    }
+
+    fn engines() -> Vec<EngineConfig> {
+        let mut quoted = EngineConfig::parse("weak").expect("The spec is valid");
+        quoted.name = "weak, \"old\"".to_string();
+        vec![EngineConfig::parse("medium@mobility").expect("The spec is valid"), quoted]
+    }
+
+    #[test]
+    fn parse_engine_specs() {
+        for spec in &["weak", "medium", "strong@classic", "timed:500", "mcts", "mcts:200"] {
+            assert_eq!(EngineConfig::parse(spec).expect("The spec is valid").name, *spec);
+        }
+        for spec in &["genius", "timed", "timed:soon", "mcts:many", "weak:5", "mcts:200@stability", "mcts@classic"] {
+            assert!(EngineConfig::parse(spec).is_err(), "Accepted {}", spec);
+        }
+    }
+
+    #[test]
+    fn timed_engines() {
+        for &(spec, timed) in &[("timed:500", true), ("strong@classic", false), ("mcts:200", false)] {
+            assert_eq!(EngineConfig::parse(spec).expect("The spec is valid").is_timed(), timed);
+        }
+    }
+
+    #[test]
+    fn schedule_pairs_of_games() {
+        let starts = [Turn::first_turn(), notation::parse_position("---------------------------OX------XO--------------------------- O")
+                          .expect("The position is valid")];
+        let pairings = paired_schedule(3, 5, 3, &starts, Some(10));
+        assert_eq!(pairings.len(), 6);
+        for (index, pairing) in pairings.iter().enumerate() {
+            // The engines swap colours within each pair, which starts from the next position
+            let (dark, light) = if index % 2 == 0 { (3, 5) } else { (5, 3) };
+            assert_eq!((pairing.dark, pairing.light), (dark, light));
+            assert_eq!(notation::position_to_string(&pairing.start),
+                       notation::position_to_string(&starts[index / 2 % starts.len()]));
+            assert_eq!(pairing.seed, Some(10 + index as u64));
+        }
+        assert!(paired_schedule(0, 1, 2, &starts, None).iter().all(|pairing| pairing.seed.is_none()));
+    }
+
+    #[test]
+    fn summarize_results() {
+        let records = [record(0, 1, 40, 24), record(1, 0, 40, 24), record(0, 1, 32, 32), record(1, 2, 10, 54)];
+        let summary = summarize(&records, 0);
+        assert_eq!((summary.wins, summary.losses, summary.draws), (1, 1, 1));
+        assert_eq!((summary.disks, summary.opponent_disks), (96, 96));
+        assert_eq!(summary.games(), 3);
+        assert_eq!(summary.points(), 1.5f64);
+        let summary = summarize(&records, 2);
+        assert_eq!((summary.wins, summary.losses, summary.draws), (1, 0, 0));
+        assert_eq!(summarize(&records, 3).games(), 0);
+    }
+
+    #[test]
+    fn read_starting_positions() {
+        let text = "# Openings\nf5d6\n\n---------------------------OX------XO--------------------------- X\n";
+        let positions = read_positions(text.as_bytes()).expect("The positions are valid");
+        assert_eq!(positions.len(), 2);
+        let after_moves = notation::replay(&Turn::first_turn(), &notation::parse_moves("f5d6").expect("The moves are valid"))
+            .expect("The moves are legal");
+        assert_eq!(notation::position_to_string(&positions[0]), notation::position_to_string(&after_moves));
+        assert_eq!(notation::position_to_string(&positions[1]), notation::position_to_string(&Turn::first_turn()));
+        for text in &["f5f5\n", "z9\n", "XO\n", &format!("{} -\n", "X".repeat(64))] {
+            assert!(read_positions(text.as_bytes()).is_err(), "Accepted {:?}", text);
+        }
+    }
+
+    #[test]
+    fn escape_strings() {
+        assert_eq!(json_string("medium"), "\"medium\"");
+        assert_eq!(json_string("a \"b\" \\ c\n"), "\"a \\\"b\\\" \\\\ c\\u000a\"");
+        assert_eq!(csv_field("timed:500@tuned.toml"), "timed:500@tuned.toml");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("a \"b\""), "\"a \"\"b\"\"\"");
+    }
+
+    #[test]
+    fn write_records() {
+        let records = [record(0, 1, 40, 24), record(1, 0, 20, 44)];
+        let start = notation::position_to_string(&Turn::first_turn());
+
+        let mut written = Vec::new();
+        write_csv(&engines(), &records, &mut written).expect("The records can be written");
+        assert_eq!(String::from_utf8(written).expect("CSV is text"),
+                   format!("dark,light,start,moves,dark_disks,light_disks\n\
+                            medium@mobility,\"weak, \"\"old\"\"\",{0},f5d6c3,40,24\n\
+                            \"weak, \"\"old\"\"\",medium@mobility,{0},f5d6c3,20,44\n",
+                           start));
+
+        let mut written = Vec::new();
+        write_json(&engines(), &records, &mut written).expect("The records can be written");
+        let json = String::from_utf8(written).expect("JSON is text");
+        // The first engine won every game, so its Elo difference is unbounded
+        assert!(json.contains("{\"name\": \"medium@mobility\", \"wins\": 2, \"losses\": 0, \"draws\": 0, \"disks\": 84, \
+                               \"opponent_disks\": 44, \"elo\": null},"));
+        assert!(json.contains("{\"name\": \"weak, \\\"old\\\"\", \"wins\": 0, \"losses\": 2"));
+        assert!(json.contains(&format!("{{\"dark\": \"medium@mobility\", \"light\": \"weak, \\\"old\\\"\", \"start\": \"{}\", \
+                                        \"moves\": \"f5d6c3\", \"dark_disks\": 40, \"light_disks\": 24}},",
+                                       start)));
+        assert!(json.trim_right().ends_with("}\n  ]\n}"));
+    }
+}
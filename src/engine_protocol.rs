@@ -0,0 +1,324 @@
+//! Provides a line-based engine protocol, so that RUSThello's AI can be driven by other programs through stdin and stdout.
+//!
+//! The protocol is a subset of NBoard's, the protocol of the NBoard Othello GUI:
+//!
+//! * `nboard <version>`: starts a session; the engine answers `set myname RUSThello`;
+//! * `ping <n>`: the engine answers `pong <n>` once it is done with the previous commands;
+//! * `set depth <plies>`: searches that many plies deep (see `DEFAULT_DEPTH`);
+//! * `set game <game>`: sets up the game, given in GGF (see the `ggf` module);
+//! * `move <move>[/<eval>/<seconds>]`: plays a move in the current game, where `pa` is a pass;
+//! * `go`: asks for a move, which the engine answers as `=== <move>/<eval>/<seconds>`;
+//! * `hint <n>`: asks for the analysis of the `n` best moves,
+//!   which the engine answers with a `search <variation> <eval> 0 <depth>` line each;
+//! * `quit`: ends the session.
+//!
+//! Other NBoard commands (e.g. `learn` or `set contempt`) are accepted and ignored.
+//! Besides those, `set time <seconds>` searches by iterative deepening for the given time instead of to a fixed depth,
+//! and `set position <position string>` sets up a game from the given position (see the `notation` module).
+//! Evaluations are from the point of view of the side to move, and are final disk differentials once the game is solved.
+//! Problems, such as illegal moves, are reported as `status <message>` lines.
+
+use ai_player::{AlphaBeta, Analysis, Score, Strength};
+use evaluation::Evaluator;
+use ggf;
+use notation;
+use reversi::Side;
+use reversi::board::Coord;
+use reversi::turn::Turn;
+use std::io::{self, BufRead, Write};
+use std::time::{Duration, Instant};
+
+/// The name the engine goes by.
+pub const ENGINE_NAME: &str = "RUSThello";
+
+/// Plies searched for each move, unless set otherwise.
+pub const DEFAULT_DEPTH: u8 = 6;
+
+/// How deep, or for how long, the engine searches.
+#[derive(Debug, Clone, Copy)]
+pub enum SearchLimit {
+    Depth(u8),
+    Time(Duration),
+}
+
+/// The state of an engine session: the game being played and the search settings.
+pub struct EngineSession {
+    evaluator: Evaluator,
+    limit: SearchLimit,
+    turn: Turn,
+    /// Kept for the whole session, so that later searches in a game start from what earlier ones stored in its table.
+    /// Its strength only sets how near the end of the game it solves exactly, as the session sets how far it searches.
+    search: AlphaBeta,
+}
+
+impl EngineSession {
+    /// Creates a session at the starting position, evaluating positions with `evaluator`.
+    pub fn new(evaluator: Evaluator) -> EngineSession {
+        EngineSession {
+            evaluator,
+            limit: SearchLimit::Depth(DEFAULT_DEPTH),
+            turn: Turn::first_turn(),
+            search: AlphaBeta::new(Strength::Medium).with_randomness(0f64),
+        }
+    }
+
+    pub fn get_turn(&self) -> &Turn {
+        &self.turn
+    }
+
+    /// Handles a command line, writing the answers to `writer`.
+    /// Returns `false` if the session has ended.
+    pub fn handle<W: Write>(&mut self, line: &str, writer: &mut W) -> io::Result<bool> {
+        let line = line.trim();
+        let (command, argument) = match line.find(char::is_whitespace) {
+            Some(index) => (&line[..index], line[index..].trim()),
+            None => (line, ""),
+        };
+        match command {
+            "" => {}
+            "nboard" => writeln!(writer, "set myname {}", ENGINE_NAME)?,
+            "ping" => writeln!(writer, "pong {}", argument)?,
+            "set" => self.set(argument, writer)?,
+            "move" => {
+                // The move can be followed by its evaluation and time, which are not needed
+                let move_string = argument.split('/').next().unwrap_or("").to_lowercase();
+                // Passes are played automatically
+                if move_string != notation::PASS {
+                    match notation::parse_coord(&move_string) {
+                        Some(coord) if self.turn.make_move(coord).is_ok() => {}
+                        _ => writeln!(writer, "status Illegal move: {}", argument)?,
+                    }
+                }
+            }
+            "go" => {
+                let start = Instant::now();
+                match self.best_move_and_score() {
+                    Some((coord, score)) => {
+                        let elapsed = start.elapsed();
+                        writeln!(writer,
+                                 "=== {}/{:.2}/{:.2}",
+                                 notation::coord_to_string(coord).to_uppercase(),
+                                 self.mover_eval(score),
+                                 elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 / 1e9)?;
+                    }
+                    None => writeln!(writer, "status The game is over")?,
+                }
+            }
+            "hint" => {
+                let count = argument.parse::<usize>().unwrap_or(1);
+                match self.analyze() {
+                    Some(analysis) => {
+                        for move_analysis in analysis.moves.iter().take(count) {
+                            let variation = notation::transcript(&self.turn, &move_analysis.variation)
+                                .expect("Variations are legal");
+                            writeln!(writer,
+                                     "search {} {:.2} 0 {}",
+                                     variation.to_uppercase(),
+                                     self.mover_eval(move_analysis.score),
+                                     analysis.depth)?;
+                        }
+                        writeln!(writer, "status")?;
+                    }
+                    None => writeln!(writer, "status The game is over")?,
+                }
+            }
+            "quit" => return Ok(false),
+            "learn" | "analyze" => {}
+            _ => writeln!(writer, "status Unknown command: {}", command)?,
+        }
+        writer.flush()?;
+        Ok(true)
+    }
+
+    /// Handles a `set` command.
+    fn set<W: Write>(&mut self, argument: &str, writer: &mut W) -> io::Result<()> {
+        let (option, value) = match argument.find(char::is_whitespace) {
+            Some(index) => (&argument[..index], argument[index..].trim()),
+            None => (argument, ""),
+        };
+        match option {
+            "depth" => {
+                match value.parse::<u8>() {
+                    Ok(depth) if depth > 0 => self.limit = SearchLimit::Depth(depth),
+                    _ => writeln!(writer, "status Invalid depth: {}", value)?,
+                }
+            }
+            "time" => {
+                match value.parse::<f64>() {
+                    Ok(seconds) if seconds > 0f64 => {
+                        self.limit = SearchLimit::Time(Duration::from_millis((seconds * 1000f64) as u64))
+                    }
+                    _ => writeln!(writer, "status Invalid time: {}", value)?,
+                }
+            }
+            "game" => {
+                match ggf::read_games(value.as_bytes()).ok().and_then(|games| games.into_iter().next()) {
+                    Some(game) => {
+                        self.turn = notation::replay(&game.start, &game.moves).expect("GGF games are checked when read");
+                        self.search.table().clear();
+                    }
+                    None => writeln!(writer, "status Invalid game: {}", value)?,
+                }
+            }
+            "position" => {
+                match notation::parse_position(value) {
+                    Some(turn) => {
+                        self.turn = turn;
+                        self.search.table().clear();
+                    }
+                    None => writeln!(writer, "status Invalid position: {}", value)?,
+                }
+            }
+            // Other settings (e.g. `myname` or `contempt`) do not apply
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Searches the current position for the best move within the search limit,
+    /// or returns `None` if the game is over.
+    fn best_move_and_score(&self) -> Option<(Coord, Score)> {
+        self.turn.get_state()?;
+        let table = self.search.table();
+        let best_move_and_score = match self.limit {
+            SearchLimit::Depth(depth) => self.search.search_to_depth(&self.turn, depth, table, &self.evaluator),
+            SearchLimit::Time(time) => self.search.search_in_time(&self.turn, time, table, &self.evaluator),
+        };
+        Some(best_move_and_score.expect("The game is not over"))
+    }
+
+    /// Analyzes every move of the current position within the search limit, or returns `None` if the game is over.
+    fn analyze(&self) -> Option<Analysis> {
+        self.turn.get_state()?;
+        let table = self.search.table();
+        let analysis = match self.limit {
+            SearchLimit::Depth(depth) => self.search.analyze_to_depth(&self.turn, depth, table, &self.evaluator),
+            SearchLimit::Time(time) => self.search.analyze_in_time(&self.turn, time, table, &self.evaluator),
+        };
+        Some(analysis.expect("The game is not over"))
+    }
+
+    /// The value of `score` from the point of view of the side to move.
+    fn mover_eval(&self, score: Score) -> f64 {
+        let value = match score {
+            Score::Running(value) => value,
+            Score::Ended(diff) => diff as f64,
+        };
+        match self.turn.get_state() {
+            Some(Side::Dark) => -value,
+            _ => value,
+        }
+    }
+}
+
+/// Runs an engine session, reading commands from `reader` and writing answers to `writer`, until `quit` or the end of input.
+pub fn run<R: BufRead, W: Write>(evaluator: Evaluator, reader: R, mut writer: W) -> io::Result<()> {
+    let mut session = EngineSession::new(evaluator);
+    for line in reader.lines() {
+        if !session.handle(&line?, &mut writer)? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EngineSession;
+    use evaluation::Evaluator;
+    use notation;
+    use reversi::turn::Turn;
+
+    /// Handles the commands of `lines` in a new session, returning the session and the lines it answered.
+    fn run_session(lines: &[&str]) -> (EngineSession, Vec<String>) {
+        let mut session = EngineSession::new(Evaluator::default());
+        let mut output = Vec::new();
+        for line in lines {
+            assert!(session.handle(line, &mut output).expect("Writing to memory does not fail"));
+        }
+        let text = String::from_utf8(output).expect("The answers are text");
+        (session, text.lines().map(str::to_string).collect())
+    }
+
+    fn position(moves: &str) -> String {
+        let moves = notation::parse_moves(moves).expect("The moves are valid");
+        notation::position_to_string(&notation::replay(&Turn::first_turn(), &moves).expect("The moves are legal"))
+    }
+
+    #[test]
+    fn greet_and_ping() {
+        let (_, answers) = run_session(&["nboard 2", "set depth 3", "ping 1", "", "set contempt 0", "learn"]);
+        assert_eq!(answers, vec!["set myname RUSThello", "pong 1"]);
+        let mut session = EngineSession::new(Evaluator::default());
+        assert!(!session.handle("quit", &mut Vec::new()).expect("Writing to memory does not fail"));
+    }
+
+    #[test]
+    fn play_moves() {
+        let (session, answers) = run_session(&["move f5", "move D6/0.50/1.2", "move c9", "move a1"]);
+        assert_eq!(answers, vec!["status Illegal move: c9", "status Illegal move: a1"]);
+        assert_eq!(notation::position_to_string(session.get_turn()), position("f5d6"));
+    }
+
+    #[test]
+    fn reject_invalid_settings() {
+        let (_, answers) = run_session(&["set depth 0", "set time soon", "set position XO", "set game (;GM[Chess];)", "dance"]);
+        assert_eq!(answers,
+                   vec!["status Invalid depth: 0",
+                        "status Invalid time: soon",
+                        "status Invalid position: XO",
+                        "status Invalid game: (;GM[Chess];)",
+                        "status Unknown command: dance"]);
+    }
+
+    #[test]
+    fn answer_go() {
+        let (session, answers) = run_session(&["set depth 2", "move f5", "go"]);
+        assert_eq!(answers.len(), 1);
+        let answer = answers[0].trim_left_matches("=== ");
+        assert!(answers[0].starts_with("=== "));
+        let fields: Vec<&str> = answer.split('/').collect();
+        assert_eq!(fields.len(), 3);
+        assert_eq!(fields[0], fields[0].to_uppercase());
+        let coord = notation::parse_coord(fields[0]).expect("The move is valid");
+        assert!(session.get_turn().check_move(coord).is_ok());
+        assert!(fields[1].parse::<f64>().is_ok() && fields[2].parse::<f64>().is_ok());
+    }
+
+    #[test]
+    fn answer_hint() {
+        let (_, answers) = run_session(&["set depth 2", "hint 3"]);
+        let (status, searches) = answers.split_last().expect("There are answers");
+        assert_eq!(status, "status");
+        // The starting position has four moves, all equivalent
+        assert_eq!(searches.len(), 3);
+        for search in searches {
+            let fields: Vec<&str> = search.split_whitespace().collect();
+            assert_eq!(fields.len(), 5);
+            assert_eq!((fields[0], fields[3], fields[4]), ("search", "0", "2"));
+            assert!(notation::parse_moves(fields[1]).is_some());
+            assert!(fields[2].parse::<f64>().is_ok());
+        }
+    }
+
+    #[test]
+    fn set_up_games() {
+        let game = "(;GM[Othello]PC[NBoard]DT[2017-05-01]PB[NBoard]PW[RUSThello]RE[?]TI[5:00]TY[8]\
+                    BO[8 ---------------------------O*------*O--------------------------- *]B[F5//1.2]W[D6/-1.50/0.3];)";
+        // The search of the starting position looks into the position after f5
+        let after_f5 = notation::parse_position(&position("f5")).expect("The position is valid");
+        let (session, _) = run_session(&["set depth 2", "go"]);
+        assert!(session.search.table().probe(session.search.table().hash(&after_f5)).is_some());
+
+        let (session, answers) = run_session(&["set depth 2", "go", &format!("set game {}", game)]);
+        assert_eq!(answers.len(), 1);
+        assert_eq!(notation::position_to_string(session.get_turn()), position("f5d6"));
+        // What was learnt about the previous game is forgotten
+        assert!(session.search.table().probe(session.search.table().hash(&after_f5)).is_none());
+
+        let (session, answers) = run_session(&["set depth 2", "go", &format!("set position {}", position("f5d6c3"))]);
+        assert_eq!(answers.len(), 1);
+        assert_eq!(notation::position_to_string(session.get_turn()), position("f5d6c3"));
+        assert!(session.search.table().probe(session.search.table().hash(&after_f5)).is_none());
+    }
+}
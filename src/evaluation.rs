@@ -0,0 +1,785 @@
+//! Provides the static evaluation of positions used by the AI.
+//! A position is described by a number of positional features, each computed from Light's point of view
+//! (positive values are good for Light, negative ones for Dark),
+//! which are then weighted differently depending on the phase of the game.
+//!
+//! Weights can be loaded from a file in a simple TOML format, e.g.
+//!
+//! ```text
+//! [corners]
+//! corner_bonus = 50
+//!
+//! [opening]
+//! mobility = 40
+//! ```
+//!
+//! Sections are `corners` (see `CornerWeights`), `opening` and `endgame` (see `Weights`);
+//! every weight left out keeps its default value.
+
+use reversi::{turn, Side};
+use reversi::board::{BOARD_SIZE, Coord};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use Result;
+
+/// Names of the built-in evaluation profiles, see `Evaluator::profile`.
+pub const PROFILES: [&str; 4] = ["default", "classic", "mobility", "stability"];
+
+/// Directions to neighbouring cells, as (row, column) steps.
+const DIRECTIONS: [(isize, isize); 8] = [(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)];
+
+/// Pairs of opposite directions, one for each line through a cell (horizontal, vertical and the two diagonals).
+const AXES: [((isize, isize), (isize, isize)); 4] = [((0, -1), (0, 1)), ((-1, 0), (1, 0)), ((-1, -1), (1, 1)), ((-1, 1), (1, -1))];
+
+/// The content of each cell, row by row.
+type Grid = [[Option<Side>; BOARD_SIZE]; BOARD_SIZE];
+
+/// Weights of the squares around the corners, used to evaluate the corner regions.
+#[derive(Debug, Clone, Copy)]
+pub struct CornerWeights {
+    /// Bonus for a corner.
+    pub corner_bonus: f64,
+    /// Malus for the cell diagonally next to an empty corner.
+    pub odd_corner_malus: f64,
+    /// Bonus for the cell diagonally two steps away from an empty corner.
+    pub even_corner_bonus: f64,
+    /// Malus for each cell next to an empty corner on the edge.
+    pub odd_malus: f64,
+    /// Bonus for each cell two steps away from an empty corner on the edge.
+    pub even_bonus: f64,
+}
+
+pub const DEFAULT_CORNER_WEIGHTS: CornerWeights = CornerWeights {
+    corner_bonus: 50f64,
+    odd_corner_malus: 20f64,
+    even_corner_bonus: 10f64,
+    odd_malus: 7f64, // x2
+    even_bonus: 3f64, // x2
+    // ------------------------ Sum = 100
+};
+
+impl CornerWeights {
+    /// The most a corner region can be worth, used to normalize the evaluation of corner regions.
+    fn total(&self) -> f64 {
+        self.corner_bonus + self.odd_corner_malus + self.even_corner_bonus + 2f64 * (self.odd_malus + self.even_bonus)
+    }
+
+    fn get_mut(&mut self, name: &str) -> Option<&mut f64> {
+        match name {
+            "corner_bonus" => Some(&mut self.corner_bonus),
+            "odd_corner_malus" => Some(&mut self.odd_corner_malus),
+            "even_corner_bonus" => Some(&mut self.even_corner_bonus),
+            "odd_malus" => Some(&mut self.odd_malus),
+            "even_bonus" => Some(&mut self.even_bonus),
+            _ => None,
+        }
+    }
+
+    fn named(&self) -> [(&'static str, f64); 5] {
+        [("corner_bonus", self.corner_bonus),
+         ("odd_corner_malus", self.odd_corner_malus),
+         ("even_corner_bonus", self.even_corner_bonus),
+         ("odd_malus", self.odd_malus),
+         ("even_bonus", self.even_bonus)]
+    }
+
+    /// Weighs the corner regions counted by `corner_counts`, normalized to range from -1 to 1.
+    pub fn weigh(&self, counts: &[f64; 5]) -> f64 {
+        let total = self.total();
+        if total > 0f64 {
+            self.named().iter().zip(counts).map(|(&(_, weight), &count)| weight * count).sum::<f64>() / total
+        } else {
+            0f64
+        }
+    }
+}
+
+/// Weights of the positional features.
+#[derive(Debug, Clone, Copy)]
+pub struct Weights {
+    pub corners: f64,
+    pub mobility: f64,
+    pub potential_mobility: f64,
+    pub frontier: f64,
+    pub stability: f64,
+    pub edges: f64,
+    pub parity: f64,
+}
+
+/// Weights used at the start of the game, when keeping options open matters most.
+pub const OPENING_WEIGHTS: Weights = Weights {
+    corners: 100f64,
+    mobility: 40f64,
+    potential_mobility: 30f64,
+    frontier: 30f64,
+    stability: 40f64,
+    edges: 15f64,
+    parity: 0f64,
+};
+
+/// Weights used at the end of the game, when disks become permanent and tempo decides the last moves.
+pub const ENDGAME_WEIGHTS: Weights = Weights {
+    corners: 60f64,
+    mobility: 20f64,
+    potential_mobility: 5f64,
+    frontier: 10f64,
+    stability: 80f64,
+    edges: 15f64,
+    parity: 20f64,
+};
+
+impl Weights {
+    fn get_mut(&mut self, name: &str) -> Option<&mut f64> {
+        match name {
+            "corners" => Some(&mut self.corners),
+            "mobility" => Some(&mut self.mobility),
+            "potential_mobility" => Some(&mut self.potential_mobility),
+            "frontier" => Some(&mut self.frontier),
+            "stability" => Some(&mut self.stability),
+            "edges" => Some(&mut self.edges),
+            "parity" => Some(&mut self.parity),
+            _ => None,
+        }
+    }
+
+    fn named(&self) -> [(&'static str, f64); 7] {
+        [("corners", self.corners),
+         ("mobility", self.mobility),
+         ("potential_mobility", self.potential_mobility),
+         ("frontier", self.frontier),
+         ("stability", self.stability),
+         ("edges", self.edges),
+         ("parity", self.parity)]
+    }
+}
+
+/// The positional features of a position, each ranging from -1 (best for Dark) to 1 (best for Light).
+#[derive(Debug, Clone, Copy)]
+pub struct Features {
+    /// Control of the corners and of the cells around them.
+    pub corners: f64,
+    /// Number of legal moves.
+    pub mobility: f64,
+    /// Number of empty cells next to opponent's disks, i.e. likely future moves.
+    pub potential_mobility: f64,
+    /// Number of own disks next to empty cells, which give the opponent moves (so fewer is better).
+    pub frontier: f64,
+    /// Number of disks which can never be flipped.
+    pub stability: f64,
+    /// Wedges available on the edges, i.e. empty edge cells between two opponent's disks.
+    pub edges: f64,
+    /// Who is expected to play the last move.
+    pub parity: f64,
+    /// How far the game has gone, from 0 (empty board) to 1 (full board).
+    pub phase: f64,
+}
+
+/// Something which can judge positions without searching any further.
+/// Evaluators are shared by the threads of a search, hence they have to be `Sync`.
+pub trait Evaluate: Sync {
+    /// Evaluates the position of `turn`: positive values are good for Light, negative ones for Dark.
+    fn evaluate(&self, turn: &turn::Turn) -> Result<f64>;
+}
+
+/// Evaluates positions by weighing their positional features.
+#[derive(Debug, Clone, Copy)]
+pub struct Evaluator {
+    pub corners: CornerWeights,
+    pub opening: Weights,
+    pub endgame: Weights,
+}
+
+impl Default for Evaluator {
+    fn default() -> Evaluator {
+        Evaluator {
+            corners: DEFAULT_CORNER_WEIGHTS,
+            opening: OPENING_WEIGHTS,
+            endgame: ENDGAME_WEIGHTS,
+        }
+    }
+}
+
+impl Evaluator {
+    /// Returns the built-in profile called `name`, if any (see `PROFILES`).
+    /// * `default`: all features, with the default weights;
+    /// * `classic`: only the corner regions, as RUSThello's AI used to play;
+    /// * `mobility`: favours mobility and quiet moves over disks;
+    /// * `stability`: favours corners and stable disks.
+    pub fn profile(name: &str) -> Option<Evaluator> {
+        let mut evaluator = Evaluator::default();
+        match name {
+            "default" => {}
+            "classic" => {
+                let corners_only = Weights {
+                    corners: 100f64,
+                    mobility: 0f64,
+                    potential_mobility: 0f64,
+                    frontier: 0f64,
+                    stability: 0f64,
+                    edges: 0f64,
+                    parity: 0f64,
+                };
+                evaluator.opening = corners_only;
+                evaluator.endgame = corners_only;
+            }
+            "mobility" => {
+                evaluator.opening.mobility *= 2f64;
+                evaluator.opening.potential_mobility *= 2f64;
+                evaluator.opening.frontier *= 2f64;
+                evaluator.endgame.mobility *= 2f64;
+            }
+            "stability" => {
+                evaluator.opening.corners *= 1.5f64;
+                evaluator.opening.stability *= 2f64;
+                evaluator.endgame.corners *= 1.5f64;
+                evaluator.endgame.stability *= 1.5f64;
+            }
+            _ => return None,
+        }
+        Some(evaluator)
+    }
+
+    /// Lists all the weights, in the order of the weights file.
+    pub fn get_parameters(&self) -> Vec<f64> {
+        let corners = self.corners.named();
+        let opening = self.opening.named();
+        let endgame = self.endgame.named();
+        corners.iter().chain(opening.iter()).chain(endgame.iter()).map(|&(_, weight)| weight).collect()
+    }
+
+    /// Sets all the weights, given in the same order as `get_parameters` lists them.
+    pub fn set_parameters(&mut self, parameters: &[f64]) {
+        let names = self.corners
+            .named()
+            .iter()
+            .map(|&(name, _)| ("corners", name))
+            .chain(self.opening.named().iter().map(|&(name, _)| ("opening", name)))
+            .chain(self.endgame.named().iter().map(|&(name, _)| ("endgame", name)))
+            .collect::<Vec<(&str, &str)>>();
+        for (&(section, name), &parameter) in names.iter().zip(parameters) {
+            *self.weight_mut(section, name).expect("Every listed weight has a name") = parameter;
+        }
+    }
+
+    /// Loads the weights from the file at `path`.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Evaluator> {
+        Evaluator::read(BufReader::new(File::open(path)?))
+    }
+
+    /// Reads the weights in the weights file format; missing weights keep their default values.
+    pub fn read<R: BufRead>(reader: R) -> io::Result<Evaluator> {
+        let mut evaluator = Evaluator::default();
+        let mut section = String::new();
+        for line in reader.lines() {
+            let line = line?;
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('[') && line.ends_with(']') {
+                section = line[1..line.len() - 1].trim().to_string();
+                continue;
+            }
+            let mut fields = line.splitn(2, '=');
+            let (name, value) = match (fields.next(), fields.next()) {
+                (Some(name), Some(value)) => (name.trim(), value.trim()),
+                _ => return Err(invalid_data(line)),
+            };
+            let weight = evaluator.weight_mut(&section, name).ok_or_else(|| invalid_data(line))?;
+            *weight = value.parse().map_err(|_| invalid_data(line))?;
+        }
+        Ok(evaluator)
+    }
+
+    /// Saves the weights to the file at `path`.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.write(BufWriter::new(File::create(path)?))
+    }
+
+    /// Writes the weights in the weights file format.
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "[corners]")?;
+        for &(name, weight) in &self.corners.named() {
+            writeln!(writer, "{} = {}", name, weight)?;
+        }
+        for &(section, weights) in &[("opening", &self.opening), ("endgame", &self.endgame)] {
+            writeln!(writer, "\n[{}]", section)?;
+            for &(name, weight) in &weights.named() {
+                writeln!(writer, "{} = {}", name, weight)?;
+            }
+        }
+        writer.flush()
+    }
+
+    fn weight_mut(&mut self, section: &str, name: &str) -> Option<&mut f64> {
+        match section {
+            "corners" => self.corners.get_mut(name),
+            "opening" => self.opening.get_mut(name),
+            "endgame" => self.endgame.get_mut(name),
+            _ => None,
+        }
+    }
+
+    /// Computes the positional features of the position of `turn`.
+    pub fn features(&self, turn: &turn::Turn) -> Result<Features> {
+        features(turn, &self.corners)
+    }
+
+    /// Blends the weights for the opening and the endgame according to the phase of the game.
+    pub fn weigh_features(&self, features: &Features) -> f64 {
+        let opening = weigh(features, &self.opening);
+        let endgame = weigh(features, &self.endgame);
+        opening * (1f64 - features.phase) + endgame * features.phase
+    }
+}
+
+/// Sums the features of a position according to `weights`.
+pub fn weigh(features: &Features, weights: &Weights) -> f64 {
+    features.corners * weights.corners + features.mobility * weights.mobility +
+    features.potential_mobility * weights.potential_mobility + features.frontier * weights.frontier +
+    features.stability * weights.stability + features.edges * weights.edges + features.parity * weights.parity
+}
+
+fn features(turn: &turn::Turn, corner_weights: &CornerWeights) -> Result<Features> {
+    let mut grid: Grid = [[None; BOARD_SIZE]; BOARD_SIZE];
+    let mut disks = 0;
+    for (row, cells) in grid.iter_mut().enumerate() {
+        for (col, cell) in cells.iter_mut().enumerate() {
+            *cell = turn.get_cell(Coord::new(row, col))?.map(|disk| disk.get_side());
+            if cell.is_some() {
+                disks += 1;
+            }
+        }
+    }
+
+    let cells: Vec<(usize, usize)> = (0..BOARD_SIZE).flat_map(|row| (0..BOARD_SIZE).map(move |col| (row, col))).collect();
+
+    let (mobility_dark, mobility_light) = count_per_side(|side| {
+        cells.iter().filter(|&&(row, col)| can_play(&grid, side, row, col)).count()
+    });
+    let (potential_dark, potential_light) = count_per_side(|side| {
+        cells.iter()
+            .filter(|&&(row, col)| grid[row][col].is_none() && has_neighbour(&grid, row, col, Some(side.opposite())))
+            .count()
+    });
+    let (frontier_dark, frontier_light) = count_per_side(|side| {
+        cells.iter()
+            .filter(|&&(row, col)| grid[row][col] == Some(side) && has_neighbour(&grid, row, col, None))
+            .count()
+    });
+    let stable = stable_disks(&grid);
+    let (stable_dark, stable_light) = count_per_side(|side| {
+        cells.iter()
+            .filter(|&&(row, col)| stable[row][col] && grid[row][col] == Some(side))
+            .count()
+    });
+    let (wedges_dark, wedges_light) = count_per_side(|side| wedges(&grid, side));
+
+    let empty_cells = BOARD_SIZE * BOARD_SIZE - disks;
+    // With an odd number of empty cells, the side to move is expected to play the last move
+    let parity = match turn.get_state() {
+        Some(Side::Light) if empty_cells % 2 == 1 => 1f64,
+        Some(Side::Dark) if empty_cells % 2 == 1 => -1f64,
+        Some(Side::Light) => -1f64,
+        Some(Side::Dark) => 1f64,
+        None => 0f64,
+    };
+
+    Ok(Features {
+           corners: corner_weights.weigh(&corner_counts(turn)?),
+           mobility: ratio(mobility_light, mobility_dark),
+           potential_mobility: ratio(potential_light, potential_dark),
+           frontier: ratio(frontier_dark, frontier_light),
+           stability: ratio(stable_light, stable_dark),
+           edges: ratio(wedges_light, wedges_dark),
+           parity,
+           phase: disks as f64 / (BOARD_SIZE * BOARD_SIZE) as f64,
+       })
+}
+
+/// Compares two quantities on a scale from -1 to 1.
+fn ratio(good: usize, bad: usize) -> f64 {
+    if good + bad == 0 {
+        0f64
+    } else {
+        (good as f64 - bad as f64) / (good + bad) as f64
+    }
+}
+
+/// Computes a quantity for Dark and for Light.
+fn count_per_side<F: Fn(Side) -> usize>(count: F) -> (usize, usize) {
+    (count(Side::Dark), count(Side::Light))
+}
+
+/// The cell one step away in the given direction, if it is on the board.
+fn step(row: usize, col: usize, (row_step, col_step): (isize, isize)) -> Option<(usize, usize)> {
+    let (new_row, new_col) = (row as isize + row_step, col as isize + col_step);
+    if new_row >= 0 && new_row < BOARD_SIZE as isize && new_col >= 0 && new_col < BOARD_SIZE as isize {
+        Some((new_row as usize, new_col as usize))
+    } else {
+        None
+    }
+}
+
+/// Whether any cell around (`row`, `col`) has content `cell`.
+fn has_neighbour(grid: &Grid, row: usize, col: usize, cell: Option<Side>) -> bool {
+    DIRECTIONS.iter().any(|&direction| match step(row, col, direction) {
+                              Some((new_row, new_col)) => grid[new_row][new_col] == cell,
+                              None => false,
+                          })
+}
+
+/// Whether `side` could legally place a disk on (`row`, `col`), regardless of whose turn it is.
+fn can_play(grid: &Grid, side: Side, row: usize, col: usize) -> bool {
+    grid[row][col].is_none() &&
+    DIRECTIONS.iter().any(|&direction| {
+        let mut flanked = 0;
+        let mut current = step(row, col, direction);
+        while let Some((new_row, new_col)) = current {
+            match grid[new_row][new_col] {
+                Some(disk_side) if disk_side == side => return flanked > 0,
+                Some(_) => flanked += 1,
+                None => return false,
+            }
+            current = step(new_row, new_col, direction);
+        }
+        false
+    })
+}
+
+/// Finds disks which can never be flipped.
+/// A disk is stable if, along each of the four lines through it, it is protected on one side
+/// (by the edge of the board or by a stable disk of the same side) or the line is full.
+fn stable_disks(grid: &Grid) -> [[bool; BOARD_SIZE]; BOARD_SIZE] {
+    let mut stable = [[false; BOARD_SIZE]; BOARD_SIZE];
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for row in 0..BOARD_SIZE {
+            for col in 0..BOARD_SIZE {
+                let side = match grid[row][col] {
+                    Some(side) if !stable[row][col] => side,
+                    _ => continue,
+                };
+                let is_stable = {
+                    let protected = |direction| match step(row, col, direction) {
+                        None => true,
+                        Some((new_row, new_col)) => stable[new_row][new_col] && grid[new_row][new_col] == Some(side),
+                    };
+                    AXES.iter().all(|&(direction1, direction2)| {
+                                        protected(direction1) || protected(direction2) ||
+                                        (is_line_full(grid, row, col, direction1) && is_line_full(grid, row, col, direction2))
+                                    })
+                };
+                if is_stable {
+                    stable[row][col] = true;
+                    changed = true;
+                }
+            }
+        }
+    }
+    stable
+}
+
+/// Whether all cells from (`row`, `col`) to the edge of the board, in the given direction, are occupied.
+fn is_line_full(grid: &Grid, row: usize, col: usize, direction: (isize, isize)) -> bool {
+    let mut current = step(row, col, direction);
+    while let Some((new_row, new_col)) = current {
+        if grid[new_row][new_col].is_none() {
+            return false;
+        }
+        current = step(new_row, new_col, direction);
+    }
+    true
+}
+
+/// Counts the wedges available to `side`: empty edge cells (corners excluded) between two opponent's disks on the same edge.
+/// Playing there is safe, as the opponent cannot flank the new disk along the edge.
+fn wedges(grid: &Grid, side: Side) -> usize {
+    let opponent = Some(side.opposite());
+    let last = BOARD_SIZE - 1;
+    let mut count = 0;
+    for index in 1..last {
+        for &((row, col), (row_before, col_before), (row_after, col_after)) in
+            &[((0, index), (0, index - 1), (0, index + 1)),
+              ((last, index), (last, index - 1), (last, index + 1)),
+              ((index, 0), (index - 1, 0), (index + 1, 0)),
+              ((index, last), (index - 1, last), (index + 1, last))] {
+            if grid[row][col].is_none() && grid[row_before][col_before] == opponent && grid[row_after][col_after] == opponent {
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+/// Counts how many times each of the corner weights applies to Light, minus how many times it applies to Dark,
+/// in the order of the weights file (see `CornerWeights::weigh`).
+/// A corner is worth the most; when it is empty, disks on the cells next to it are a liability,
+/// while disks on the cells two steps away are a small asset.
+pub fn corner_counts(turn: &turn::Turn) -> Result<[f64; 5]> {
+    let sides: [(Coord, Coord, Coord, Coord, Coord, Coord, Coord); 4] = [(/* NW corner */
+                                                                          Coord::new(0, 0),
+                                                                          Coord::new(0, 1),
+                                                                          Coord::new(1, 1),
+                                                                          Coord::new(0, 2),
+                                                                          Coord::new(2, 2),
+                                                                          Coord::new(1, 0),
+                                                                          Coord::new(2, 0)),
+                                                                         (/* NE corner */
+                                                                          Coord::new(0, 7),
+                                                                          Coord::new(1, 7),
+                                                                          Coord::new(1, 6),
+                                                                          Coord::new(2, 7),
+                                                                          Coord::new(2, 5),
+                                                                          Coord::new(0, 6),
+                                                                          Coord::new(0, 5)),
+                                                                         (/* SW corner */
+                                                                          Coord::new(7, 0),
+                                                                          Coord::new(6, 0),
+                                                                          Coord::new(6, 1),
+                                                                          Coord::new(5, 0),
+                                                                          Coord::new(5, 2),
+                                                                          Coord::new(7, 1),
+                                                                          Coord::new(7, 2)),
+                                                                         (/* SE corner */
+                                                                          Coord::new(7, 7),
+                                                                          Coord::new(6, 7),
+                                                                          Coord::new(6, 6),
+                                                                          Coord::new(5, 7),
+                                                                          Coord::new(5, 5),
+                                                                          Coord::new(7, 6),
+                                                                          Coord::new(7, 5))];
+
+    let (corner_bonus, odd_corner_malus, even_corner_bonus, odd_malus, even_bonus) = (0, 1, 2, 3, 4);
+    let mut counts = [0f64; 5];
+
+    for &(corner, odd, odd_corner, even, even_corner, counter_odd, counter_even) in &sides {
+
+        if let Some(disk) = *turn.get_cell(corner)? {
+            match disk.get_side() {
+                Side::Light => counts[corner_bonus] += 1f64,
+                Side::Dark => counts[corner_bonus] -= 1f64,
+            }
+        } else {
+            for &(coord_odd, coord_even) in &[(odd, even), (counter_odd, counter_even)] {
+                if let Some(disk) = *turn.get_cell(coord_odd)? {
+                    match disk.get_side() {
+                        Side::Light => counts[odd_malus] -= 1f64,
+                        Side::Dark => counts[odd_malus] += 1f64,
+                    }
+                } else if let Some(disk) = *turn.get_cell(coord_even)? {
+                    match disk.get_side() {
+                        Side::Light => counts[even_bonus] += 1f64,
+                        Side::Dark => counts[even_bonus] -= 1f64,
+                    }
+                }
+            }
+            if let Some(disk) = *turn.get_cell(odd_corner)? {
+                match disk.get_side() {
+                    Side::Light => counts[odd_corner_malus] -= 1f64,
+                    Side::Dark => counts[odd_corner_malus] += 1f64,
+                }
+
+            } else if let Some(disk) = *turn.get_cell(even_corner)? {
+                match disk.get_side() {
+                    Side::Light => counts[even_corner_bonus] += 1f64,
+                    Side::Dark => counts[even_corner_bonus] -= 1f64,
+                }
+            }
+        }
+    }
+    Ok(counts)
+}
+
+impl Evaluate for Evaluator {
+    /// Weighs the positional features of the position (see `weigh_features`).
+    fn evaluate(&self, turn: &turn::Turn) -> Result<f64> {
+        Ok(self.weigh_features(&self.features(turn)?))
+    }
+}
+
+fn invalid_data(line: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("Invalid weights line: {}", line))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{can_play, stable_disks, wedges, Evaluator, Grid, PROFILES};
+    use notation;
+    use reversi::Side;
+    use reversi::board::{BOARD_SIZE, Coord};
+    use reversi::turn::Turn;
+
+    /// Builds a grid from rows written as in position strings.
+    fn grid(rows: &[&str; BOARD_SIZE]) -> Grid {
+        let mut grid: Grid = [[None; BOARD_SIZE]; BOARD_SIZE];
+        for (row, line) in rows.iter().enumerate() {
+            for (col, c) in line.chars().enumerate() {
+                grid[row][col] = match c {
+                    'X' => Some(Side::Dark),
+                    'O' => Some(Side::Light),
+                    _ => None,
+                };
+            }
+        }
+        grid
+    }
+
+    /// Reads the grid of a position.
+    fn grid_of(turn: &Turn) -> Grid {
+        let mut grid: Grid = [[None; BOARD_SIZE]; BOARD_SIZE];
+        for (row, cells) in grid.iter_mut().enumerate() {
+            for (col, cell) in cells.iter_mut().enumerate() {
+                *cell = turn.get_cell(Coord::new(row, col)).expect("The cell is on the board").map(|disk| disk.get_side());
+            }
+        }
+        grid
+    }
+
+    fn position(moves: &str) -> Turn {
+        let moves = notation::parse_moves(moves).expect("The moves are valid");
+        notation::replay(&Turn::first_turn(), &moves).expect("The moves are legal")
+    }
+
+    #[test]
+    fn write_and_read_weights() {
+        let mut evaluator = Evaluator::default();
+        let parameters: Vec<f64> = (0..evaluator.get_parameters().len()).map(|index| index as f64 * 1.25f64 - 3f64).collect();
+        evaluator.set_parameters(&parameters);
+        let mut written = Vec::new();
+        evaluator.write(&mut written).expect("The weights can be written");
+        let read = Evaluator::read(&written[..]).expect("The written weights are valid");
+        assert_eq!(read.get_parameters(), parameters);
+    }
+
+    #[test]
+    fn read_partial_weights() {
+        let text = "# Comments and missing weights are fine\n[corners]\ncorner_bonus = 70 # a bigger bonus\n\n[endgame]\nmobility=-2.5\n";
+        let evaluator = Evaluator::read(text.as_bytes()).expect("The weights are valid");
+        let default = Evaluator::default();
+        assert_eq!(evaluator.corners.corner_bonus, 70f64);
+        assert_eq!(evaluator.endgame.mobility, -2.5f64);
+        assert_eq!(evaluator.corners.odd_malus, default.corners.odd_malus);
+        assert_eq!(evaluator.opening.mobility, default.opening.mobility);
+    }
+
+    #[test]
+    fn reject_invalid_weights() {
+        for text in &["[corners]\nbonus = 1\n",
+                      "[middlegame]\nmobility = 1\n",
+                      "mobility = 1\n",
+                      "[opening]\nmobility = lots\n",
+                      "[opening]\nmobility\n"] {
+            assert!(Evaluator::read(text.as_bytes()).is_err(), "Accepted {:?}", text);
+        }
+    }
+
+    #[test]
+    fn profiles() {
+        for name in &PROFILES {
+            assert!(Evaluator::profile(name).is_some(), "Missing profile {}", name);
+        }
+        assert!(Evaluator::profile("unknown").is_none());
+        assert_eq!(Evaluator::profile("default").map(|evaluator| evaluator.get_parameters()),
+                   Some(Evaluator::default().get_parameters()));
+        let classic = Evaluator::profile("classic").expect("The profile exists");
+        assert_eq!(classic.opening.mobility, 0f64);
+        assert_eq!(classic.endgame.corners, 100f64);
+    }
+
+    #[test]
+    fn filled_edge_is_stable() {
+        let stable = stable_disks(&grid(&["XXXXOOOO", "-O------", "--------", "--------", "--------", "--------", "--------", "--------"]));
+        assert!(stable[0].iter().all(|&cell| cell));
+        // b2 can still be flipped along its diagonal
+        assert!(!stable[1][1]);
+    }
+
+    #[test]
+    fn x_square_next_to_empty_corner_is_not_stable() {
+        let stable = stable_disks(&grid(&["-OO-----", "OXX-----", "OX------", "--------", "--------", "--------", "--------", "--------"]));
+        assert!(!stable[1][1]);
+        assert!(!stable[0][1]);
+
+        // Once the corner is taken, the disks around it are anchored to it
+        let stable = stable_disks(&grid(&["OOO-----", "OOX-----", "OX------", "--------", "--------", "--------", "--------", "--------"]));
+        assert!(stable[0][0] && stable[0][1] && stable[1][0] && stable[1][1]);
+        assert!(!stable[1][2]);
+    }
+
+    #[test]
+    fn wedges_between_opponent_disks() {
+        let edges = grid(&["O-O-XX-X", "--------", "--------", "--------", "--------", "--------", "--------", "O-O--X--"]);
+        // b1 and b8 for Dark, g1 for Light; d1 lies between disks of both sides and d8 next to a single one
+        assert_eq!(wedges(&edges, Side::Dark), 2);
+        assert_eq!(wedges(&edges, Side::Light), 1);
+
+        let evaluator = Evaluator::default();
+        let turn = notation::parse_position("O-O-----\
+                                             --------\
+                                             --------\
+                                             ---XO---\
+                                             ---OX---\
+                                             --------\
+                                             --------\
+                                             -------- X")
+            .expect("The position is valid");
+        // Dark's wedge on b1 against none for Light
+        assert_eq!(evaluator.features(&turn).expect("The position is on the board").edges, -1f64);
+    }
+
+    #[test]
+    fn parity_follows_empty_cells() {
+        let evaluator = Evaluator::default();
+        let parity = |string: &str| {
+            let turn = notation::parse_position(string).expect("The position is valid");
+            evaluator.features(&turn).expect("The position is on the board").parity
+        };
+        let start = notation::position_to_string(&Turn::first_turn());
+        let after_f5 = notation::position_to_string(&position("f5"));
+        // 60 empty cells: Light is expected to play last, whoever moves now
+        assert_eq!(parity(&start), 1f64);
+        assert_eq!(parity(&start.replace(" X", " O")), -1f64);
+        // 59 empty cells: the side to move is expected to play last
+        assert_eq!(parity(&after_f5), 1f64);
+        assert_eq!(parity(&after_f5.replace(" O", " X")), -1f64);
+    }
+
+    #[test]
+    fn mobility_matches_legal_moves() {
+        let evaluator = Evaluator::default();
+        // Plays a whole game, always taking the last legal move, and checks every position on the way
+        let mut turn = Turn::first_turn();
+        while let Some(side) = turn.get_state() {
+            let cells = grid_of(&turn);
+            let mut last_move = None;
+            for row in 0..BOARD_SIZE {
+                for col in 0..BOARD_SIZE {
+                    let legal = turn.check_move(Coord::new(row, col)).is_ok();
+                    assert_eq!(can_play(&cells, side, row, col), legal);
+                    if legal {
+                        last_move = Some(Coord::new(row, col));
+                    }
+                }
+            }
+            turn.make_move(last_move.expect("The side to move has a legal move")).expect("The move is legal");
+        }
+
+        // Both sides have four moves at the start
+        let features = evaluator.features(&Turn::first_turn()).expect("The position is on the board");
+        assert_eq!(features.mobility, 0f64);
+    }
+
+    #[test]
+    fn potential_mobility_favours_surrounded_opponents() {
+        let evaluator = Evaluator::default();
+        let start = evaluator.features(&Turn::first_turn()).expect("The position is on the board");
+        assert_eq!(start.potential_mobility, 0f64);
+        // After f5, Light's single disk leaves Dark few cells to aim at, while Dark's four disks give Light many
+        let after_f5 = evaluator.features(&position("f5")).expect("The position is on the board");
+        assert!(after_f5.potential_mobility > 0f64);
+        assert!(after_f5.frontier > 0f64);
+    }
+}
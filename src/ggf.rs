@@ -0,0 +1,303 @@
+//! Provides import and export of games in the Generic Game Format (GGF), used by many Othello programs and servers.
+//!
+//! A GGF game is enclosed in `(;` and `;)` and made of `KEY[value]` properties, e.g.
+//!
+//! ```text
+//! (;GM[Othello]PC[RUSThello]PB[human]PW[medium]TY[8]RE[?]BO[8 -------- -------- -------- ---O*--- ---*O--- -------- -------- -------- *]B[f5]W[d6//2.50];)
+//! ```
+//!
+//! Moves are written as `B[<move>/<evaluation>/<seconds>]` or `W[...]`, where the evaluation and the time are optional,
+//! and the result is the disk differential from Black's (that is, Dark's) point of view.
+//! The starting position of the game is given by the `BO` property, with `*` for Black's disks and `O` for White's.
+
+use notation;
+use reversi::Side;
+use reversi::board::{BOARD_SIZE, Coord};
+use reversi::turn::Turn;
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::Path;
+
+/// A game of Othello as recorded in GGF.
+#[derive(Debug, Clone)]
+pub struct GgfGame {
+    /// The names of the players.
+    pub dark: String,
+    pub light: String,
+    /// The time control in GGF format (e.g. `05:00//02:00`), if the game was timed.
+    pub time_control: Option<String>,
+    /// The position the game started from.
+    pub start: Turn,
+    /// The moves from the start of the game, without passes.
+    pub moves: Vec<Coord>,
+    /// Seconds spent on each of the moves, where recorded.
+    pub move_times: Vec<Option<f64>>,
+    /// The final disk differential (Light's disks minus Dark's disks), if the game is over.
+    pub result: Option<i16>,
+}
+
+impl GgfGame {
+    /// Creates an untimed game of the given players from the position of `start`,
+    /// taking the result from the position reached by `moves`. Returns `None` if any move is illegal.
+    pub fn new(dark: &str, light: &str, start: &Turn, moves: &[Coord]) -> Option<GgfGame> {
+        let mut turn = *start;
+        for &coord in moves {
+            if turn.make_move(coord).is_err() {
+                return None;
+            }
+        }
+        Some(GgfGame {
+                 dark: dark.to_string(),
+                 light: light.to_string(),
+                 time_control: None,
+                 start: *start,
+                 moves: moves.to_vec(),
+                 move_times: Vec::new(),
+                 result: match turn.get_state() {
+                     Some(_) => None,
+                     None => Some(turn.get_score_diff()),
+                 },
+             })
+    }
+
+    /// Saves the game to the file at `path`.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.write(BufWriter::new(File::create(path)?))
+    }
+
+    /// Writes the game in GGF, on a single line, with the passes.
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        write!(writer, "(;GM[Othello]PC[RUSThello]PB[{}]PW[{}]", self.dark, self.light)?;
+        if let Some(ref time_control) = self.time_control {
+            write!(writer, "TI[{}]", time_control)?;
+        }
+        match self.result {
+            // GGF results are from Black's point of view
+            Some(diff) => write!(writer, "TY[{}]RE[{:+.3}]", BOARD_SIZE, -diff as f64)?,
+            None => write!(writer, "TY[{}]RE[?]", BOARD_SIZE)?,
+        }
+
+        let mut turn = self.start;
+        write!(writer, "BO[{}]", board_string(&turn))?;
+        for (index, &coord) in self.moves.iter().enumerate() {
+            let side = turn.get_state().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Move after the end of the game"))?;
+            turn.make_move(coord)
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "Illegal move in game"))?;
+            write!(writer, "{}[{}", side_tag(side), notation::coord_to_string(coord))?;
+            if let Some(Some(seconds)) = self.move_times.get(index).cloned() {
+                write!(writer, "//{:.2}", seconds)?;
+            }
+            write!(writer, "]")?;
+            // The opponent passes if the same side is still to move
+            if turn.get_state() == Some(side) {
+                write!(writer, "{}[{}]", side_tag(side.opposite()), notation::PASS.to_uppercase())?;
+            }
+        }
+        writeln!(writer, ";)")?;
+        writer.flush()
+    }
+}
+
+/// Loads all the games in the GGF file at `path`.
+pub fn load_games<P: AsRef<Path>>(path: P) -> io::Result<Vec<GgfGame>> {
+    read_games(File::open(path)?)
+}
+
+/// Reads all the games in GGF from `reader`, checking that their moves are legal.
+/// Text outside of games is ignored.
+pub fn read_games<R: Read>(mut reader: R) -> io::Result<Vec<GgfGame>> {
+    let mut text = String::new();
+    reader.read_to_string(&mut text)?;
+    let mut games = Vec::new();
+    let mut rest = &*text;
+    while let Some(start) = rest.find("(;") {
+        let end = rest[start..].find(";)").ok_or_else(|| invalid_data("Unterminated game"))?;
+        games.push(parse_game(&rest[start + 2..start + end])?);
+        rest = &rest[start + end + 2..];
+    }
+    Ok(games)
+}
+
+/// Parses the properties of a game, found between `(;` and `;)`.
+fn parse_game(text: &str) -> io::Result<GgfGame> {
+    let mut game = GgfGame {
+        dark: String::new(),
+        light: String::new(),
+        time_control: None,
+        start: Turn::first_turn(),
+        moves: Vec::new(),
+        move_times: Vec::new(),
+        result: None,
+    };
+    let mut turn = Turn::first_turn();
+    let mut rest = text.trim_left();
+    while !rest.is_empty() {
+        let open = rest.find('[').ok_or_else(|| invalid_data(rest))?;
+        let close = rest.find(']').ok_or_else(|| invalid_data(rest))?;
+        if close < open {
+            return Err(invalid_data(rest));
+        }
+        let key = rest[..open].trim();
+        let value = rest[open + 1..close].trim();
+        match key {
+            "GM" if value != "Othello" => return Err(invalid_data(value)),
+            "TY" if value != BOARD_SIZE.to_string() => return Err(invalid_data(value)),
+            "BO" => {
+                // The board size comes before the cells, which are written as in position strings
+                let cells = value.trim_left_matches(char::is_numeric);
+                if !game.moves.is_empty() {
+                    return Err(invalid_data(value));
+                }
+                game.start = notation::parse_position(cells).ok_or_else(|| invalid_data(value))?;
+                turn = game.start;
+            }
+            "PB" => game.dark = value.to_string(),
+            "PW" => game.light = value.to_string(),
+            "TI" => game.time_control = Some(value.to_string()),
+            "RE" => {
+                // Results can be followed by how the game ended, e.g. `+64.000:r` for resignation
+                game.result = value.split(':')
+                    .next()
+                    .and_then(|diff| diff.parse::<f64>().ok())
+                    .map(|diff| -diff.round() as i16);
+            }
+            "B" | "W" => {
+                let side = if key == "B" { Side::Dark } else { Side::Light };
+                let mut fields = value.split('/');
+                let move_string = fields.next().unwrap_or("");
+                if move_string.to_lowercase() == notation::PASS {
+                    if turn.get_state() == Some(side) {
+                        return Err(invalid_data(value));
+                    }
+                } else if turn.get_state() != Some(side) {
+                    return Err(invalid_data(value));
+                } else {
+                    let coord = notation::parse_coord(move_string).ok_or_else(|| invalid_data(value))?;
+                    turn.make_move(coord).map_err(|_| invalid_data(value))?;
+                    game.moves.push(coord);
+                    game.move_times.push(fields.nth(1).and_then(|seconds| seconds.parse::<f64>().ok()));
+                }
+            }
+            // Other properties (place, date, ratings, comments...) are not needed
+            _ => {}
+        }
+        rest = rest[close + 1..].trim_left();
+    }
+    Ok(game)
+}
+
+/// The position of `turn` in GGF, row by row, followed by the side to move.
+fn board_string(turn: &Turn) -> String {
+    let mut string = format!("{}", BOARD_SIZE);
+    for row in 0..BOARD_SIZE {
+        string.push(' ');
+        for col in 0..BOARD_SIZE {
+            string.push(match *turn.get_cell(Coord::new(row, col)).expect("Coordinates are within the board") {
+                            Some(disk) if disk.get_side() == Side::Dark => '*',
+                            Some(_) => 'O',
+                            None => '-',
+                        });
+        }
+    }
+    string.push(' ');
+    string.push(match turn.get_state() {
+                    Some(Side::Light) => 'O',
+                    _ => '*',
+                });
+    string
+}
+
+/// The GGF tag of the moves of `side`.
+fn side_tag(side: Side) -> &'static str {
+    match side {
+        Side::Dark => "B",
+        Side::Light => "W",
+    }
+}
+
+fn invalid_data(text: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("Invalid GGF: {}", text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_games, GgfGame};
+    use notation;
+    use reversi::turn::Turn;
+
+    /// A position from which White has to pass once during `PASSING_MOVES`.
+    const PASSING_START: &str = "-X-------O-------O------XXO------------------------------------- X";
+    const PASSING_MOVES: &str = "c2a2d4d1d3d2e4e2";
+
+    fn write_and_read(game: &GgfGame) -> GgfGame {
+        let mut written = Vec::new();
+        game.write(&mut written).expect("The game can be written");
+        let mut games = read_games(&written[..]).expect("The written game is valid");
+        assert_eq!(games.len(), 1);
+        games.remove(0)
+    }
+
+    #[test]
+    fn write_and_read_game_with_passes() {
+        let start = notation::parse_position(PASSING_START).expect("The position is valid");
+        let moves = notation::parse_moves(PASSING_MOVES).expect("The moves are valid");
+        let mut game = GgfGame::new("medium", "human", &start, &moves).expect("The moves are legal");
+        game.move_times = vec![Some(1.5f64); moves.len()];
+        let mut written = Vec::new();
+        game.write(&mut written).expect("The game can be written");
+        let text = String::from_utf8(written).expect("GGF is text");
+        assert!(text.contains("BO[8 -*------ -O------ -O------ **O----- -------- -------- -------- -------- *]"));
+        assert!(text.contains("[PA]"));
+        assert!(text.contains("RE[?]"));
+
+        let read = write_and_read(&game);
+        assert_eq!((&*read.dark, &*read.light), ("medium", "human"));
+        assert_eq!(notation::position_to_string(&read.start), PASSING_START);
+        assert_eq!(read.moves, moves);
+        assert_eq!(read.move_times, game.move_times);
+        assert_eq!(read.result, None);
+    }
+
+    #[test]
+    fn write_and_read_finished_game() {
+        let start = notation::parse_position(&format!("XO{} X", "-".repeat(62))).expect("The position is valid");
+        let moves = notation::parse_moves("c1").expect("The moves are valid");
+        let game = GgfGame::new("weak", "strong", &start, &moves).expect("The moves are legal");
+        // Results are from Light's point of view, but from Black's in GGF
+        assert_eq!(game.result, Some(-3));
+        let mut written = Vec::new();
+        game.write(&mut written).expect("The game can be written");
+        assert!(String::from_utf8(written).expect("GGF is text").contains("RE[+3.000]"));
+        assert_eq!(write_and_read(&game).result, Some(-3));
+    }
+
+    #[test]
+    fn read_move_suffixes() {
+        let text = "Ignored text (;GM[Othello]PC[NBoard]PB[a]PW[b]TI[05:00//02:00]RE[-4.000:t]B[F5/1.50/2.25]W[d6//0.5]B[c3];)";
+        let games = read_games(text.as_bytes()).expect("The game is valid");
+        assert_eq!(games.len(), 1);
+        let game = &games[0];
+        assert_eq!(game.moves, notation::parse_moves("f5d6c3").expect("The moves are valid"));
+        assert_eq!(game.move_times, vec![Some(2.25f64), Some(0.5f64), None]);
+        assert_eq!(game.time_control, Some("05:00//02:00".to_string()));
+        assert_eq!(game.result, Some(4));
+        assert_eq!(notation::position_to_string(&game.start), notation::position_to_string(&Turn::first_turn()));
+    }
+
+    #[test]
+    fn reject_invalid_games() {
+        for text in &["(;GM[Chess];)",
+                      "(;GM[Othello]TY[10];)",
+                      "(;GM[Othello]B[f5]",
+                      "(;GM[Othello]B[f5;)",
+                      "(;GM[Othello]W[f5];)",
+                      "(;GM[Othello]B[f5]B[d6];)",
+                      "(;GM[Othello]B[z9];)",
+                      "(;GM[Othello]B[f4];)",
+                      "(;GM[Othello]B[PA];)",
+                      "(;GM[Othello]BO[8 -------- *];)",
+                      "(;GM[Othello]B[f5]BO[8 -------- -------- -------- ---O*--- ---*O--- -------- -------- -------- *];)"] {
+            assert!(read_games(text.as_bytes()).is_err(), "Accepted {:?}", text);
+        }
+    }
+}
@@ -1,12 +1,18 @@
 //! This module provides interface functionalities and manages all the input/output part of the program
 
 use std::string::String;
+use std::fmt;
 use std::io::{self, Write};
+use std::sync::atomic::{self, AtomicBool, ATOMIC_BOOL_INIT};
 use reversi::Side;
 use reversi::board::{BOARD_SIZE, Coord};
 use reversi::game::PlayerAction;
 use reversi::turn::{State, Turn};
 use {Result, Action, OtherAction};
+use ai_player::{Score, Strength};
+use evaluation::PROFILES;
+use notation;
+use review::{Judgement, MoveReview};
 use termion::{color, style};
 
 // ANSI version
@@ -14,13 +20,71 @@ const DARK_DISK: char = '●';
 const LIGHT_DISK: char = '●';
 const EMPTY_CELL: char = '∙';
 const LEGAL_MOVE: char = '○';
+const HINT_MOVE: char = '◎';
+
+// Plain version, without colors
+const PLAIN_DARK_DISK: char = 'X';
+const PLAIN_LIGHT_DISK: char = 'O';
+const PLAIN_EMPTY_CELL: char = '.';
+const PLAIN_LEGAL_MOVE: char = '+';
+const PLAIN_HINT_MOVE: char = '*';
+
+/// Set when colors and styles are disabled, e.g. because the output is read by another program.
+static NO_COLORS: AtomicBool = ATOMIC_BOOL_INIT;
+
+/// Disables colors and styles in all the output.
+pub fn disable_colors() {
+    NO_COLORS.store(true, atomic::Ordering::Relaxed);
+}
+
+/// A color or a style, written only if colors are enabled.
+struct Ansi<T>(T);
+
+impl<T: fmt::Display> fmt::Display for Ansi<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if NO_COLORS.load(atomic::Ordering::Relaxed) {
+            Ok(())
+        } else {
+            self.0.fmt(f)
+        }
+    }
+}
+
+fn ansi<T: fmt::Display>(escape: T) -> Ansi<T> {
+    Ansi(escape)
+}
+
+fn fg<C: color::Color>(c: C) -> Ansi<color::Fg<C>> {
+    Ansi(color::Fg(c))
+}
+
+fn bg<C: color::Color>(c: C) -> Ansi<color::Bg<C>> {
+    Ansi(color::Bg(c))
+}
+
+/// The character drawn for a cell, in the plain version if colors are disabled.
+fn glyph(colored: char, plain: char) -> char {
+    if NO_COLORS.load(atomic::Ordering::Relaxed) {
+        plain
+    } else {
+        colored
+    }
+}
+
+/// Strength of the AI giving hints, unless the user asks for another one.
+const DEFAULT_HINT_STRENGTH: Strength = Strength::Medium;
 
 pub enum UserCommand {
     NewGame,
+    LoadGame,
+    SetupPosition,
+    LoadBook,
+    LoadWeights,
     HumanPlayer,
     AiWeak,
     AiMedium,
     AiStrong,
+    AiMcts,
     Help,
     Credits,
     Quit,
@@ -34,11 +98,11 @@ fn ruler() -> String {
 
 fn header(title: &str) -> String {
 	let formatted_title = format!("\t{:-^1$}", String::from(" ") + title + " ", COLUMN_WIDTH as usize);
-    format!("\n\n\n {}{}\n{}\n{}{}", style::Bold,
+    format!("\n\n\n {}{}\n{}\n{}{}", ansi(style::Bold),
 	 								ruler(),
 									formatted_title,
 									ruler(),
-								 	style::Reset)
+								 	ansi(style::Reset))
 }
 
 const INTRO: &'static str = "\t  a simple Reversi game
@@ -49,6 +113,10 @@ pub fn intro() {
 }
 
 const MAIN_MENU: &'static str = "\tn - New match
+\tl - Load game
+\tp - Set up a position
+\to - Load opening book
+\te - Load evaluation weights
 \th - Help
 \tc - Credits
 \tq - Quit RUSThello";
@@ -61,6 +129,7 @@ const NEW_PLAYER_MENU: &'static str = "\th - Human Player
 \tw - Weak   AI
 \tm - Medium AI
 \ts - Strong AI
+\tc - Monte Carlo AI
 \tq - Quit match";
 
 pub fn new_player_menu() {
@@ -72,7 +141,9 @@ const COMMANDS_INFO: &'static str = "\n\n
 \tType a cell's coordinates to place your disk there.
 \tExaple: \"c4\" (or \"C4\", \"4c\", \"4C\", etc...).
 \tType 'help' or 'h' to display a help message.
+\tType 'hint' or 't' to ask the AI for a hint.
 \tType 'undo' or 'u' to undo the last move.
+\tType 'save <file>' or 'load <file>' to save the game or load another one.
 \tType 'quit' or 'q' to abandon the game.";
 
 pub fn commands_info() {
@@ -110,6 +181,14 @@ E.g. all of 'c4', 'C4', '4c' and '4C' are valid and equivalent coordinates. \
 For ease of use, all legal moves on the board are highlighted.\n
 \tFurthermore, you can also input special commands:
 \t* 'undo' (or 'u') to undo your last move (and yes, you can 'undo' as many times as you like),
+\t* 'hint' (or 't') to have an AI suggest a move, highlighted on the board; \
+add 'weak', 'medium' or 'strong' (e.g. 'hint strong') to choose how hard it thinks,
+\t* 'save <file>' to save the game to a file, and 'load <file>' to leave it for a game saved before, \
+with its players and all of its moves (so they can still be undone); \
+files ending in '.ggf' are in the Generic Game Format used by other Othello programs,
+\t* 'setup <position>' to start a new game from the given position, with the same players, \
+where the position is written as its 64 cells row by row ('X' for Dark, 'O' for Light and '-' for empty) \
+followed by the side to move ('X' or 'O'); 'setup' alone shows the current position written that way,
 \t* 'help' (or 'h') to see this help message again, and 'quit' (or 'q') to quit the game.";
 
 pub fn help() {
@@ -126,15 +205,31 @@ pub fn credits() {
 		env!("CARGO_PKG_VERSION"));
 }
 
-/// Reads user's input
-fn get_user_input() -> String {
+/// Reads user's input as it is, only trimming whitespace
+fn get_raw_user_input() -> String {
     let _ = io::stdout().flush();
     let mut input = String::new();
     if io::stdin().read_line(&mut input).is_err() {
         panic!("\tFailed to read input!");
     }
-    input = input.trim().to_lowercase();
-    input
+    input.trim().to_string()
+}
+
+/// Reads user's input
+fn get_user_input() -> String {
+    get_raw_user_input().to_lowercase()
+}
+
+/// Asks the user for the path of a file.
+pub fn input_file_path() -> String {
+    print!("\tInsert file path: ");
+    get_raw_user_input()
+}
+
+/// Asks the user for the name of an evaluation profile or the path of a weights file.
+pub fn input_weights() -> String {
+    print!("\tInsert profile ({}) or file path: ", PROFILES.join(", "));
+    get_raw_user_input()
 }
 
 /// It gets an input from the user and tries to parse it, then returns a `Option<UserCommand>`.
@@ -145,6 +240,10 @@ pub fn input_main_menu() -> UserCommand {
     loop {
         match &*get_user_input() {
             "n" | "new game" => return UserCommand::NewGame,
+            "l" | "load" | "load game" => return UserCommand::LoadGame,
+            "p" | "position" | "setup" => return UserCommand::SetupPosition,
+            "o" | "book" | "opening book" => return UserCommand::LoadBook,
+            "e" | "weights" | "evaluation weights" => return UserCommand::LoadWeights,
             "h" | "help" => return UserCommand::Help,
             "c" | "credits" => return UserCommand::Credits,
             "q" | "quit" | "exit" => return UserCommand::Quit,
@@ -158,8 +257,8 @@ pub fn input_main_menu() -> UserCommand {
 
 pub fn choose_new_player(side: Side) -> UserCommand {
     match side {
-        Side::Dark => print!("\t{}Dark{}  player: ", style::Bold, style::Reset),
-        Side::Light => print!("\t{}Light{} player: ", style::Bold, style::Reset),
+        Side::Dark => print!("\t{}Dark{}  player: ", ansi(style::Bold), ansi(style::Reset)),
+        Side::Light => print!("\t{}Light{} player: ", ansi(style::Bold), ansi(style::Reset)),
     }
     loop {
         match &*get_user_input() {
@@ -167,6 +266,7 @@ pub fn choose_new_player(side: Side) -> UserCommand {
             "w" | "weak" | "weak ai" => return UserCommand::AiWeak,
             "m" | "medium" | "medium ai" => return UserCommand::AiMedium,
             "s" | "strong" | "strong ai" => return UserCommand::AiStrong,
+            "c" | "monte carlo" | "monte carlo ai" | "mcts" => return UserCommand::AiMcts,
             "q" | "quit" | "exit" => return UserCommand::Quit,
             _ => {
                 print!("\tInvalid command! Try again: ");
@@ -176,24 +276,128 @@ pub fn choose_new_player(side: Side) -> UserCommand {
     }
 }
 
+const POSITION_EDITOR: &str = "\tType a cell's coordinates followed by 'x', 'o' or '-' \
+to put a Dark disk, a Light disk or nothing there (e.g. 'd3 x').
+\tType 'side x' or 'side o' to choose who moves first.
+\tType 'clear' to empty the board, or 'reset' to go back to the starting position.
+\tPaste a position string to replace the whole position.
+\tType 'done' to play from the position, or 'quit' (or 'q') to go back to the main menu.";
+
+/// Lets the user build a position cell by cell, or by pasting its position string.
+/// Returns `None` if the user quits the editor.
+pub fn edit_position() -> Option<Turn> {
+    println!("{}\n{}", header("POSITION EDITOR"), POSITION_EDITOR);
+    let mut turn = Turn::first_turn();
+    let mut position = notation::position_to_string(&turn);
+    loop {
+        draw_board(&turn);
+        print!("\tPosition: {}\n\tEdit position: ", position);
+        loop {
+            let raw_input = get_raw_user_input();
+            let input = &*raw_input.to_lowercase();
+            let words: Vec<&str> = input.split_whitespace().collect();
+            let mut cells: Vec<char> = position.chars().collect();
+            match (words.first().cloned(), words.get(1).cloned(), words.len()) {
+                (Some("q"), None, _) |
+                (Some("quit"), None, _) => return None,
+                (Some("done"), None, _) => {
+                    if turn.get_state().is_none() {
+                        print!("\tNo one can move in this position! Keep editing: ");
+                        continue;
+                    }
+                    return Some(turn);
+                }
+                (Some("clear"), None, _) => {
+                    for cell in &mut cells[..BOARD_SIZE * BOARD_SIZE] {
+                        *cell = '-';
+                    }
+                }
+                (Some("reset"), None, _) => cells = notation::position_to_string(&Turn::first_turn()).chars().collect(),
+                (Some("side"), Some("x"), 2) => cells[BOARD_SIZE * BOARD_SIZE + 1] = 'X',
+                (Some("side"), Some("o"), 2) => cells[BOARD_SIZE * BOARD_SIZE + 1] = 'O',
+                (Some(cell), Some(content), 2) if notation::parse_coord(cell).is_some() && ["x", "o", "-"].contains(&content) => {
+                    let coord = notation::parse_coord(cell).expect("The cell was checked already");
+                    cells[coord.get_row() * BOARD_SIZE + coord.get_col()] = content.to_uppercase()
+                        .chars()
+                        .next()
+                        .expect("The content is not empty");
+                }
+                _ => {
+                    match notation::parse_position(&raw_input) {
+                        Some(pasted_turn) => cells = notation::position_to_string(&pasted_turn).chars().collect(),
+                        None => {
+                            print!("\tInvalid command! Try again: ");
+                            continue;
+                        }
+                    }
+                }
+            }
+            // Once edited, a position where the game was over may be playable again: Dark moves first unless told otherwise
+            if cells[BOARD_SIZE * BOARD_SIZE + 1] == '-' {
+                cells[BOARD_SIZE * BOARD_SIZE + 1] = 'X';
+            }
+            let edited_position: String = cells.into_iter().collect();
+            match notation::parse_position(&edited_position) {
+                Some(edited_turn) => {
+                    turn = edited_turn;
+                    position = edited_position;
+                    break;
+                }
+                None => print!("\tInvalid position! Try again: "),
+            }
+        }
+    }
+}
+
 /// It `get_status` a human player's input and convert it into a move.
 /// If the move if illegal, it ask for another input until the given move is a legal one.
 pub fn human_make_move(turn: &Turn) -> Result<Action> {
 
     if let Some(side) = turn.get_state() {
         match side {
-            Side::Dark => print!("\t{}Dark{}  moves: ", style::Bold, style::Reset),
-            Side::Light => print!("\t{}Light{} moves: ", style::Bold, style::Reset),
+            Side::Dark => print!("\t{}Dark{}  moves: ", ansi(style::Bold), ansi(style::Reset)),
+            Side::Light => print!("\t{}Light{} moves: ", ansi(style::Bold), ansi(style::Reset)),
         }
     } else {
         unreachable!();
     }
 
     loop {
-        let input = &*get_user_input();
+        let raw_input = get_raw_user_input();
+        let input = &*raw_input.to_lowercase();
+
+        // Saving and loading take a file path, which is not lowercased
+        let mut words = raw_input.splitn(2, char::is_whitespace);
+        match (words.next().map(str::to_lowercase), words.next().map(str::trim)) {
+            (Some(ref command), Some(path)) if command == "save" && !path.is_empty() => {
+                return Ok(PlayerAction::Other(OtherAction::Save(path.to_string())))
+            }
+            (Some(ref command), Some(path)) if command == "load" && !path.is_empty() => {
+                return Ok(PlayerAction::Other(OtherAction::Load(path.to_string())))
+            }
+            (Some(ref command), Some(position)) if command == "setup" && !position.is_empty() => {
+                match notation::parse_position(position) {
+                    Some(setup_turn) => return Ok(PlayerAction::Other(OtherAction::Setup(setup_turn))),
+                    None => {
+                        print!("\tInvalid position, try again: ");
+                        continue;
+                    }
+                }
+            }
+            _ => {}
+        }
+
         match input {
             "h" | "help" => return Ok(PlayerAction::Other(OtherAction::Help)),
+            "t" | "hint" => return Ok(PlayerAction::Other(OtherAction::Hint(DEFAULT_HINT_STRENGTH))),
+            "hint w" | "hint weak" => return Ok(PlayerAction::Other(OtherAction::Hint(Strength::Weak))),
+            "hint m" | "hint medium" => return Ok(PlayerAction::Other(OtherAction::Hint(Strength::Medium))),
+            "hint s" | "hint strong" => return Ok(PlayerAction::Other(OtherAction::Hint(Strength::Strong))),
             "u" | "undo" => return Ok(PlayerAction::Undo),
+            "setup" => {
+                print!("\tCurrent position: {}\n\tYour move: ", notation::position_to_string(turn));
+                continue;
+            }
             "q" | "quit" => return Ok(PlayerAction::Other(OtherAction::Quit)),
             _other_input => {
                 let mut row: Option<usize> = None;
@@ -226,21 +430,26 @@ pub fn human_make_move(turn: &Turn) -> Result<Action> {
 
 /// `draw_board` draws the board (using text characters) in a pleasant-looking way.
 pub fn draw_board(turn: &Turn) {
+    draw_board_with_hint(turn, None)
+}
+
+/// Draws the board like `draw_board`, highlighting the `hint` move if there is one.
+pub fn draw_board_with_hint(turn: &Turn, hint: Option<Coord>) {
     let board = turn.get_board();
     let stdout = io::stdout();
     let mut board_to_string = stdout.lock();
     // Add column reference at the top
     write!(board_to_string,
            "\n\t{}                         {}\n",
-           color::Bg(color::LightGreen),
-           color::Bg(color::Reset))
+           bg(color::LightGreen),
+           bg(color::Reset))
             .expect("Writing on buffer `board_to_string` failed!");
     write!(board_to_string,
            "\t{}{}     A B C D E F G H     {}{}\n",
-           color::Bg(color::LightGreen),
-           color::Fg(color::Black),
-           color::Fg(color::Reset),
-           color::Bg(color::Reset))
+           bg(color::LightGreen),
+           fg(color::Black),
+           fg(color::Reset),
+           bg(color::Reset))
             .expect("Writing on buffer `board_to_string` failed!");
 
     // For every row…
@@ -248,14 +457,14 @@ pub fn draw_board(turn: &Turn) {
         // Add a row reference to the left
         write!(board_to_string,
                "\t{}  {}{}{} {}",
-               color::Bg(color::LightGreen),
-               color::Fg(color::Black),
+               bg(color::LightGreen),
+               fg(color::Black),
                row + 1,
-               color::Fg(color::Reset),
-               color::Bg(color::Reset))
+               fg(color::Reset),
+               bg(color::Reset))
                 .expect("Writing on buffer `board_to_string` failed!");
         // Set background color to green
-        write!(board_to_string, "{} ", color::Bg(color::Green)).expect("Writing on buffer `board_to_string` failed!");
+        write!(board_to_string, "{} ", bg(color::Green)).expect("Writing on buffer `board_to_string` failed!");
         // For every column, add the appropriate character depending on the content of the current cell
         for col in 0..BOARD_SIZE {
             let coord = Coord::new(row, col);
@@ -266,16 +475,16 @@ pub fn draw_board(turn: &Turn) {
                             Side::Dark => {
                                 write!(board_to_string,
                                        "{}{}{}",
-                                       color::Fg(color::Black),
-                                       DARK_DISK,
-                                       color::Fg(color::Reset))
+                                       fg(color::Black),
+                                       glyph(DARK_DISK, PLAIN_DARK_DISK),
+                                       fg(color::Reset))
                             }
                             Side::Light => {
                                 write!(board_to_string,
                                        "{}{}{}",
-                                       color::Fg(color::LightWhite),
-                                       LIGHT_DISK,
-                                       color::Fg(color::Reset))
+                                       fg(color::LightWhite),
+                                       glyph(LIGHT_DISK, PLAIN_LIGHT_DISK),
+                                       fg(color::Reset))
                             }
                         }
                         .expect("Writing on buffer `board_to_string` failed!");
@@ -283,21 +492,28 @@ pub fn draw_board(turn: &Turn) {
                 // An empty cell will display a plus or a multiplication sign if the current player can move in that cell
                 // or a little central dot otherwise
                 None => {
-                    if turn.check_move(coord).is_ok() {
+                    if hint == Some(coord) {
+                        write!(board_to_string,
+                               "{}{}{}",
+                               fg(color::LightYellow),
+                               glyph(HINT_MOVE, PLAIN_HINT_MOVE),
+                               fg(color::Reset))
+                                .expect("Writing on buffer `board_to_string` failed!");
+                    } else if turn.check_move(coord).is_ok() {
                         match turn.get_state() {
                                 Some(Side::Dark) => {
                                     write!(board_to_string,
                                            "{}{}{}",
-                                           color::Fg(color::LightBlack),
-                                           LEGAL_MOVE,
-                                           color::Fg(color::Reset))
+                                           fg(color::LightBlack),
+                                           glyph(LEGAL_MOVE, PLAIN_LEGAL_MOVE),
+                                           fg(color::Reset))
                                 } // style::Faint, style::NoFaint,
                                 Some(Side::Light) => {
                                     write!(board_to_string,
                                            "{}{}{}",
-                                           color::Fg(color::White),
-                                           LEGAL_MOVE,
-                                           color::Fg(color::Reset))
+                                           fg(color::White),
+                                           glyph(LEGAL_MOVE, PLAIN_LEGAL_MOVE),
+                                           fg(color::Reset))
                                 } // style::Faint, style::NoFaint,
                                 None => panic!("This should never happen!"),
                             }
@@ -305,9 +521,9 @@ pub fn draw_board(turn: &Turn) {
                     } else {
                         write!(board_to_string,
                                "{}{}{}",
-                               color::Fg(color::LightGreen),
-                               EMPTY_CELL,
-                               color::Fg(color::Reset))
+                               fg(color::LightGreen),
+                               glyph(EMPTY_CELL, PLAIN_EMPTY_CELL),
+                               fg(color::Reset))
                                 .expect("Writing on buffer `board_to_string` failed!");
                     }
                 }
@@ -315,86 +531,86 @@ pub fn draw_board(turn: &Turn) {
             write!(board_to_string, " ").expect("Writing on buffer `board_to_string` failed!");
         }
         // Reset background color
-        write!(board_to_string, "{}", color::Bg(color::Reset)).expect("Writing on buffer `board_to_string` failed!");
+        write!(board_to_string, "{}", bg(color::Reset)).expect("Writing on buffer `board_to_string` failed!");
 
         // Add a row reference to the right
         write!(board_to_string,
                "{} {}{}{}  {}\n",
-               color::Bg(color::LightGreen),
-               color::Fg(color::Black),
+               bg(color::LightGreen),
+               fg(color::Black),
                row + 1,
-               color::Fg(color::Reset),
-               color::Bg(color::Reset))
+               fg(color::Reset),
+               bg(color::Reset))
                 .expect("Writing on buffer `board_to_string` failed!");
     }
 
     // Add column reference at the bottom
     write!(board_to_string,
            "\t{}{}     A B C D E F G H     {}{}",
-           color::Bg(color::LightGreen),
-           color::Fg(color::Black),
-           color::Fg(color::Reset),
-           color::Bg(color::Reset))
+           bg(color::LightGreen),
+           fg(color::Black),
+           fg(color::Reset),
+           bg(color::Reset))
             .expect("Writing on buffer `board_to_string` failed!");
 
     // Print current score and game info
     let (score_dark, score_light) = turn.get_score();
     write!(board_to_string,
            "\n\t{}                         {}",
-           color::Bg(color::LightGreen),
-           color::Bg(color::Reset))
+           bg(color::LightGreen),
+           bg(color::Reset))
             .expect("Writing on buffer `board_to_string` failed!");
     write!(board_to_string,
            "\n\t{}{}       {:>2}{} ",
-           color::Bg(color::LightGreen),
-           color::Fg(color::Black),
+           bg(color::LightGreen),
+           fg(color::Black),
            score_dark,
-           color::Fg(color::Reset))
+           fg(color::Reset))
             .expect("Writing on buffer `board_to_string` failed!");
     match turn.get_state() {
             Some(side) if side == Side::Dark => {
                 write!(board_to_string,
                        "{}{}{}{}{}   {}{}{}",
-                       color::Fg(color::Black),
-                       style::Blink,
-                       DARK_DISK,
-                       style::NoBlink,
-                       color::Fg(color::Reset),
-                       color::Fg(color::LightWhite),
-                       LIGHT_DISK,
-                       color::Fg(color::Reset))
+                       fg(color::Black),
+                       ansi(style::Blink),
+                       glyph(DARK_DISK, PLAIN_DARK_DISK),
+                       ansi(style::NoBlink),
+                       fg(color::Reset),
+                       fg(color::LightWhite),
+                       glyph(LIGHT_DISK, PLAIN_LIGHT_DISK),
+                       fg(color::Reset))
             }
             Some(side) if side == Side::Light => {
                 write!(board_to_string,
                        "{}{}{}   {}{}{}{}{}",
-                       color::Fg(color::Black),
-                       DARK_DISK,
-                       color::Fg(color::Reset),
-                       color::Fg(color::LightWhite),
-                       style::Blink,
-                       LIGHT_DISK,
-                       style::NoBlink,
-                       color::Fg(color::Reset))
+                       fg(color::Black),
+                       glyph(DARK_DISK, PLAIN_DARK_DISK),
+                       fg(color::Reset),
+                       fg(color::LightWhite),
+                       ansi(style::Blink),
+                       glyph(LIGHT_DISK, PLAIN_LIGHT_DISK),
+                       ansi(style::NoBlink),
+                       fg(color::Reset))
             }
             None => {
                 write!(board_to_string,
                        "{}{}{}   {}{}{}",
-                       color::Fg(color::Black),
-                       DARK_DISK,
-                       color::Fg(color::Reset),
-                       color::Fg(color::LightWhite),
-                       LIGHT_DISK,
-                       color::Fg(color::Reset))
+                       fg(color::Black),
+                       glyph(DARK_DISK, PLAIN_DARK_DISK),
+                       fg(color::Reset),
+                       fg(color::LightWhite),
+                       glyph(LIGHT_DISK, PLAIN_LIGHT_DISK),
+                       fg(color::Reset))
             }
             _ => unreachable!(),
         }
         .expect("Writing on buffer `board_to_string` failed!");
     write!(board_to_string,
            " {}{:<2}       {}{}\n\n",
-           color::Fg(color::LightWhite),
+           fg(color::LightWhite),
            score_light,
-           color::Fg(color::Reset),
-           color::Bg(color::Reset))
+           fg(color::Reset),
+           bg(color::Reset))
             .expect("Writing on buffer `board_to_string` failed!");
     board_to_string
         .flush()
@@ -407,15 +623,15 @@ pub fn move_message(side: Side, coord: Coord) {
     match side {
         Side::Dark => {
             println!("\t{}Dark{}  moves: {}{}",
-                     style::Bold,
-                     style::Reset,
+                     ansi(style::Bold),
+                     ansi(style::Reset),
                      char_col,
                      coord.get_row() + 1)
         }
         Side::Light => {
             println!("\t{}Light{} moves: {}{}",
-                     style::Bold,
-                     style::Reset,
+                     ansi(style::Bold),
+                     ansi(style::Reset),
                      char_col,
                      coord.get_row() + 1)
         }
@@ -425,9 +641,9 @@ pub fn move_message(side: Side, coord: Coord) {
 /// Print a message to declare winner
 pub fn endgame_message(winner: Option<Side>) {
     match winner {
-        Some(Side::Dark) => println!("\t{}Dark wins{}!", style::Bold, style::Reset),
-        Some(Side::Light) => println!("\t{}Light wins{}!", style::Bold, style::Reset),
-        None => println!("\t{}Tie{}!", style::Bold, style::Reset),
+        Some(Side::Dark) => println!("\t{}Dark wins{}!", ansi(style::Bold), ansi(style::Reset)),
+        Some(Side::Light) => println!("\t{}Light wins{}!", ansi(style::Bold), ansi(style::Reset)),
+        None => println!("\t{}Tie{}!", ansi(style::Bold), ansi(style::Reset)),
     }
 
 }
@@ -437,15 +653,15 @@ pub fn quitting_message(state: State) {
     match state {
         Some(Side::Dark) => {
             println!("\t{}Dark{} is running away, the coward!",
-                     style::Bold,
-                     style::Reset)
+                     ansi(style::Bold),
+                     ansi(style::Reset))
         }
         Some(Side::Light) => {
             println!("\t{}Light{} is running away, the coward!",
-                     style::Bold,
-                     style::Reset)
+                     ansi(style::Bold),
+                     ansi(style::Reset))
         }
-        None => println!("\n\t{}Goodbye!{}", style::Bold, style::Reset),
+        None => println!("\n\t{}Goodbye!{}", ansi(style::Bold), ansi(style::Reset)),
     }
 }
 
@@ -454,13 +670,155 @@ pub fn no_undo_message(undecided: Side) {
     match undecided {
         Side::Dark => {
             println!("\tThere is no move {}Dark{} can undo.",
-                     style::Bold,
-                     style::Reset)
+                     ansi(style::Bold),
+                     ansi(style::Reset))
         }
         Side::Light => {
             println!("\tThere is no move {}Light{} can undo.",
-                     style::Bold,
-                     style::Reset)
+                     ansi(style::Bold),
+                     ansi(style::Reset))
+        }
+    }
+}
+
+/// Print a message when an opening book has been loaded
+pub fn book_loaded_message(positions: usize) {
+    println!("\tOpening book loaded: {}{}{} positions.",
+             ansi(style::Bold),
+             positions,
+             ansi(style::Reset))
+}
+
+/// Print a message when a file could not be read or written
+pub fn file_error_message(err: &io::Error) {
+    println!("\tSomething went wrong with the file: {}", err)
+}
+
+/// Print a message when evaluation weights have been loaded
+pub fn weights_loaded_message(name: &str) {
+    println!("\tEvaluation weights loaded: {}{}{}.",
+             ansi(style::Bold),
+             name,
+             ansi(style::Reset))
+}
+
+/// Print the move suggested by a hint, with its score from the point of view of the side to move
+pub fn hint_message(side: Side, coord: Coord, score: Score) {
+    println!("\tHint: {}{}{} ({})",
+             ansi(style::Bold),
+             notation::coord_to_string(coord),
+             ansi(style::Reset),
+             outlook(side, score))
+}
+
+/// Describes `score` from the point of view of `side`
+fn outlook(side: Side, score: Score) -> String {
+    // Scores are positive when good for Light
+    let sign: i16 = match side {
+        Side::Dark => -1,
+        Side::Light => 1,
+    };
+    match score {
+        Score::Running(val) => format!("evaluation {:+.1}", sign as f64 * val),
+        Score::Ended(diff) if sign * diff > 0 => format!("wins by {}", diff.abs()),
+        Score::Ended(diff) if sign * diff < 0 => format!("loses by {}", diff.abs()),
+        Score::Ended(_) => "draws".to_string(),
+    }
+}
+
+/// Asks the user whether to review the game just ended.
+pub fn input_review() -> bool {
+    print!("\tReview the game? (y/n): ");
+    loop {
+        match &*get_user_input() {
+            "y" | "yes" => return true,
+            "n" | "no" => return false,
+            _ => {
+                print!("\tInvalid command! Try again: ");
+                continue;
+            }
+        }
+    }
+}
+
+/// Prints the review of a game, listing the moves which lost evaluation and how many each side made
+pub fn review(reviews: &[MoveReview]) {
+    println!("{}", header("REVIEW"));
+    for (number, move_review) in reviews.iter().enumerate() {
+        let judgement = match move_review.judgement {
+            Some(Judgement::Inaccuracy) => "inaccuracy",
+            Some(Judgement::Mistake) => "mistake",
+            Some(Judgement::Blunder) => "blunder",
+            None => continue,
+        };
+        println!("\t{:>2}. {}{:<5}{} {}: {}{}{} ({}), best was {} ({})",
+                 number + 1,
+                 ansi(style::Bold),
+                 match move_review.side {
+                     Side::Dark => "Dark",
+                     Side::Light => "Light",
+                 },
+                 ansi(style::Reset),
+                 notation::coord_to_string(move_review.coord),
+                 ansi(style::Bold),
+                 judgement,
+                 ansi(style::Reset),
+                 outlook(move_review.side, move_review.score),
+                 notation::coord_to_string(move_review.best_move),
+                 outlook(move_review.side, move_review.best_score));
+    }
+    for &(side, name) in &[(Side::Dark, "Dark"), (Side::Light, "Light")] {
+        let count = |judgement| {
+            reviews.iter()
+                .filter(|move_review| move_review.side == side && move_review.judgement == Some(judgement))
+                .count()
+        };
+        println!("\t{}{:<5}{}: {} inaccuracies, {} mistakes, {} blunders",
+                 ansi(style::Bold),
+                 name,
+                 ansi(style::Reset),
+                 count(Judgement::Inaccuracy),
+                 count(Judgement::Mistake),
+                 count(Judgement::Blunder));
+    }
+}
+
+/// Print a message when the game has been saved
+pub fn game_saved_message(path: &str) {
+    println!("\tGame saved to {}{}{}.",
+             ansi(style::Bold),
+             path,
+             ansi(style::Reset))
+}
+
+/// Asks the user whether to save the transcript of the game just ended.
+pub fn input_transcript() -> bool {
+    print!("\tSave the transcript of the game? (y/n): ");
+    loop {
+        match &*get_user_input() {
+            "y" | "yes" => return true,
+            "n" | "no" => return false,
+            _ => {
+                print!("\tInvalid command! Try again: ");
+                continue;
+            }
         }
     }
 }
+
+/// Print a message when the transcript of the game has been saved
+pub fn transcript_saved_message(path: &str, transcript: &str) {
+    println!("\tTranscript {} saved to {}{}{}.",
+             transcript,
+             ansi(style::Bold),
+             path,
+             ansi(style::Reset))
+}
+
+/// Print the results of a series of games
+pub fn results_message(dark_wins: usize, light_wins: usize, draws: usize) {
+    println!("{}", header("RESULTS"));
+    println!("\t{}Dark{}  wins: {}", ansi(style::Bold), ansi(style::Reset), dark_wins);
+    println!("\t{}Light{} wins: {}", ansi(style::Bold), ansi(style::Reset), light_wins);
+    println!("\t{}Ties{}:       {}", ansi(style::Bold), ansi(style::Reset), draws);
+}
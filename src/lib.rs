@@ -12,13 +12,39 @@ extern crate termion;
 pub mod interface;
 pub mod human_player;
 pub mod ai_player;
+pub mod mcts_player;
+pub mod transposition_table;
+pub mod endgame_solver;
+pub mod evaluation;
+pub mod notation;
+pub mod ggf;
+pub mod wthor;
+pub mod opening_book;
+pub mod tuning;
+pub mod review;
+pub mod saved_game;
+pub mod setup_game;
+pub mod engine_match;
+pub mod tournament;
+pub mod elo;
+pub mod engine_protocol;
 
+use ai_player::Strength;
 use reversi::{ReversiError};
 use reversi::game::{PlayerAction};
+use reversi::turn::Turn;
 use std::result;
 
 pub enum OtherAction {
     Help,
+    /// Asks an AI of the given strength for a hint.
+    Hint(Strength),
+    /// Saves the game to the file at the given path.
+    Save(String),
+    /// Abandons the game for the one saved in the file at the given path.
+    Load(String),
+    /// Starts a new game from the given position, with the same players.
+    Setup(Turn),
     Quit,
 }
 
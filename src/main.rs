@@ -13,13 +13,94 @@ extern crate rusthello_lib;
 extern crate reversi;
 
 use reversi::{ReversiError, Side};
-use reversi::game::{PlayerAction, IsPlayer, Game};
+use reversi::game::{PlayerAction, IsPlayer};
 use rusthello_lib::{OtherAction, Result};
-use rusthello_lib::{interface, human_player, ai_player};
+use rusthello_lib::{interface, human_player, mcts_player, notation, review};
+use rusthello_lib::ai_player::{AiPlayer, AlphaBeta, Strength, RANDOMNESS};
+use rusthello_lib::evaluation::Evaluator;
 use rusthello_lib::interface::{UserCommand};
+use rusthello_lib::opening_book::{BookPlayer, OpeningBook};
+use reversi::board::Coord;
+use reversi::turn::Turn;
+use rusthello_lib::saved_game::{PlayerKind, SavedGame, ScriptedPlayer};
+use rusthello_lib::setup_game::SetupGame;
+use std::cell::RefCell;
 use std::cmp::Ordering;
+use std::collections::VecDeque;
+use std::env;
+use std::fs::File;
+use std::io::{self, Write};
+use std::process;
+use std::rc::Rc;
+use std::result;
+use std::sync::Arc;
+
+/// Strength of the AI reviewing games.
+const REVIEW_STRENGTH: Strength = Strength::Medium;
+
+const USAGE: &str = "Usage: rusthello [--dark <player>] [--light <player>] [--load <file>] [--seed <seed>] \
+[--no-color] [--games <number>]
+Players are human, weak, medium, strong or mcts. With any option but --seed and --no-color, \
+the games start straight away, asking only for the players which are not given.";
+
+/// The settings of the games to play.
+#[derive(Clone)]
+struct Settings {
+    /// The opening book used by AI players, if any
+    book: Option<Arc<OpeningBook>>,
+    /// The evaluation weights used by AI players
+    evaluator: Evaluator,
+    /// If set, AI players are seeded with it, so that games can be replayed exactly
+    seed: Option<u64>,
+    /// Whether to offer the transcript and the review of games when they end
+    post_game: bool,
+}
+
+/// The options given on the command line.
+#[derive(Default)]
+struct Options {
+    dark: Option<PlayerKind>,
+    light: Option<PlayerKind>,
+    load: Option<String>,
+    seed: Option<u64>,
+    no_colors: bool,
+    games: Option<usize>,
+}
+
+/// How a match ended.
+enum MatchEnd {
+    /// The game is over, with the given disk differential (Light's disks minus Dark's disks).
+    Over(i16),
+    Quit,
+    /// The user loaded or set up another game, which replaces the match.
+    Replaced(SavedGame),
+}
 
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", USAGE);
+        return;
+    }
+    let options = parse_options(&args).unwrap_or_else(|err| exit_with_error(&format!("{}\n{}", err, USAGE)));
+    if options.no_colors {
+        interface::disable_colors();
+    }
+
+    let mut settings = Settings {
+        book: None,
+        evaluator: Evaluator::default(),
+        seed: options.seed,
+        // Series of games are meant to be played without interruptions
+        post_game: options.games.is_none(),
+    };
+
+    // Play straight away if the command line sets up the games
+    if options.dark.is_some() || options.light.is_some() || options.load.is_some() || options.games.is_some() {
+        play_from_command_line(&options, &settings);
+        return;
+    }
+
     // Main intro
     interface::intro();
 
@@ -29,10 +110,58 @@ fn main() {
         match interface::input_main_menu() {
             // Runs the game
             UserCommand::NewGame => {
-                if play_game().is_err() {
+                if play_game(&settings, None).is_err() {
                     panic!("Match ended with an error!");
                 }
             }
+            // Resumes a saved game
+            UserCommand::LoadGame => {
+                match SavedGame::load(interface::input_file_path()) {
+                    Ok(saved_game) => {
+                        if play_game(&settings, Some(saved_game)).is_err() {
+                            panic!("Match ended with an error!");
+                        }
+                    }
+                    Err(err) => interface::file_error_message(&err),
+                }
+            }
+            // Runs a game from a position built by the user
+            UserCommand::SetupPosition => {
+                if let Some(setup) = interface::edit_position().and_then(choose_players) {
+                    if play_game(&settings, Some(setup)).is_err() {
+                        panic!("Match ended with an error!");
+                    }
+                }
+            }
+            // Loads an opening book for AI players
+            UserCommand::LoadBook => {
+                match OpeningBook::load(interface::input_file_path()) {
+                    Ok(new_book) => {
+                        interface::book_loaded_message(new_book.len());
+                        settings.book = Some(Arc::new(new_book));
+                    }
+                    Err(err) => interface::file_error_message(&err),
+                }
+            }
+            // Loads the evaluation weights of AI players, either a built-in profile or a weights file
+            UserCommand::LoadWeights => {
+                let name = interface::input_weights();
+                match Evaluator::profile(&name) {
+                    Some(profile) => {
+                        settings.evaluator = profile;
+                        interface::weights_loaded_message(&name);
+                    }
+                    None => {
+                        match Evaluator::load(&name) {
+                            Ok(weights) => {
+                                settings.evaluator = weights;
+                                interface::weights_loaded_message(&name);
+                            }
+                            Err(err) => interface::file_error_message(&err),
+                        }
+                    }
+                }
+            }
             // Prints help message
             UserCommand::Help => interface::help(),
             // Print credits
@@ -47,44 +176,144 @@ fn main() {
     }
 }
 
-fn play_game() -> Result<()> {
+/// Reads the command line options.
+fn parse_options(args: &[String]) -> result::Result<Options, String> {
+    let mut options = Options::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match &**arg {
+            "--no-color" => options.no_colors = true,
+            "--dark" | "--light" | "--load" | "--seed" | "--games" => {
+                let value = args.next().ok_or_else(|| format!("Missing value for {}", arg))?;
+                let invalid = || format!("Invalid value for {}: {}", arg, value);
+                match &**arg {
+                    "--dark" => options.dark = Some(PlayerKind::from_name(value).ok_or_else(&invalid)?),
+                    "--light" => options.light = Some(PlayerKind::from_name(value).ok_or_else(&invalid)?),
+                    "--load" => options.load = Some(value.clone()),
+                    "--seed" => options.seed = Some(value.parse().map_err(|_| invalid())?),
+                    "--games" => {
+                        match value.parse() {
+                            Ok(games) if games > 0 => options.games = Some(games),
+                            _ => return Err(invalid()),
+                        }
+                    }
+                    _ => unreachable!(),
+                }
+            }
+            _ => return Err(format!("Unknown option: {}", arg)),
+        }
+    }
+    Ok(options)
+}
 
-    // Get the two players
-    interface::new_player_menu();
-    let mut dark_human = false;
-    let dark = match interface::choose_new_player(Side::Dark) {
-        UserCommand::Quit => return Ok(()),
-        UserCommand::HumanPlayer => {
-            dark_human = true;
-            Box::new(human_player::HumanPlayer) as Box<IsPlayer<OtherAction>>
+/// Plays the games set up on the command line, asking the user only for the players it leaves out.
+fn play_from_command_line(options: &Options, settings: &Settings) {
+    let mut setup = match options.load {
+        Some(ref path) => SavedGame::load(path).unwrap_or_else(|err| exit_with_error(&format!("Cannot read {}: {}", path, err))),
+        None => {
+            if options.dark.is_none() || options.light.is_none() {
+                interface::new_player_menu();
+            }
+            let dark = match options.dark.or_else(|| player_kind(interface::choose_new_player(Side::Dark))) {
+                Some(kind) => kind,
+                None => return,
+            };
+            let light = match options.light.or_else(|| player_kind(interface::choose_new_player(Side::Light))) {
+                Some(kind) => kind,
+                None => return,
+            };
+            SavedGame {
+                dark,
+                light,
+                start: Turn::first_turn(),
+                moves: Vec::new(),
+            }
         }
-        UserCommand::AiWeak => Box::new(ai_player::AiPlayer::Weak) as Box<IsPlayer<OtherAction>>,
-        UserCommand::AiMedium => Box::new(ai_player::AiPlayer::Medium) as Box<IsPlayer<OtherAction>>,
-        UserCommand::AiStrong => Box::new(ai_player::AiPlayer::Strong) as Box<IsPlayer<OtherAction>>,
-        _ => panic!("Returned an invalid player choice"),
     };
-    let mut light_human = false;
-    let light = match interface::choose_new_player(Side::Light) {
-        UserCommand::Quit => return Ok(()),
-        UserCommand::HumanPlayer => {
-            light_human = true;
-            Box::new(human_player::HumanPlayer) as Box<IsPlayer<OtherAction>>
+    // Players given on the command line replace the ones of a loaded game
+    setup.dark = options.dark.unwrap_or(setup.dark);
+    setup.light = options.light.unwrap_or(setup.light);
+
+    let games = options.games.unwrap_or(1);
+    let (mut dark_wins, mut light_wins, mut draws) = (0, 0, 0);
+    for game in 0..games {
+        // Each game of a series gets its own seed, or they would all be the same
+        let game_settings = Settings { seed: settings.seed.map(|seed| seed.wrapping_add(game as u64)), ..settings.clone() };
+        match play_game(&game_settings, Some(setup.clone())) {
+            Ok(Some(diff)) if diff < 0 => dark_wins += 1,
+            Ok(Some(diff)) if diff > 0 => light_wins += 1,
+            Ok(Some(_)) => draws += 1,
+            Ok(None) => break,
+            Err(_) => panic!("Match ended with an error!"),
         }
-        UserCommand::AiWeak => Box::new(ai_player::AiPlayer::Weak) as Box<IsPlayer<OtherAction>>,
-        UserCommand::AiMedium => Box::new(ai_player::AiPlayer::Medium) as Box<IsPlayer<OtherAction>>,
-        UserCommand::AiStrong => Box::new(ai_player::AiPlayer::Strong) as Box<IsPlayer<OtherAction>>,
-        _ => panic!("Returned an invalid player choice"),
-    };
+    }
+    if games > 1 {
+        interface::results_message(dark_wins, light_wins, draws);
+    }
+}
+
+/// Plays a game, either `saved_game` or a new one between players chosen by the user.
+/// Returns the final disk differential (Light's disks minus Dark's disks), or `None` if the user quit.
+fn play_game(settings: &Settings, saved_game: Option<SavedGame>) -> Result<Option<i16>> {
+    let mut saved_game = saved_game;
+    loop {
+        let setup = match saved_game.take() {
+            Some(setup) => setup,
+            None => {
+                match choose_players(Turn::first_turn()) {
+                    Some(setup) => setup,
+                    None => return Ok(None),
+                }
+            }
+        };
+        match play_match(settings, setup)? {
+            MatchEnd::Over(diff) => return Ok(Some(diff)),
+            MatchEnd::Quit => return Ok(None),
+            // A game loaded or set up from within the match replaces it
+            MatchEnd::Replaced(new_game) => saved_game = Some(new_game),
+        }
+    }
+}
+
+/// Asks the user for the two players of a new game from the position of `start`.
+/// Returns `None` if the user quit.
+fn choose_players(start: Turn) -> Option<SavedGame> {
+    interface::new_player_menu();
+    let dark = player_kind(interface::choose_new_player(Side::Dark))?;
+    let light = player_kind(interface::choose_new_player(Side::Light))?;
+    Some(SavedGame {
+             dark,
+             light,
+             start,
+             moves: Vec::new(),
+         })
+}
+
+/// Plays a match from the position reached by the moves of `setup`.
+fn play_match(settings: &Settings, setup: SavedGame) -> Result<MatchEnd> {
+
+    // The moves of the setup are played first, restoring the history of the game
+    let script = Rc::new(RefCell::new(setup.moves.iter().cloned().collect::<VecDeque<Coord>>()));
+    let dark = ScriptedPlayer::new(script.clone(), new_player(setup.dark, settings));
+    let light = ScriptedPlayer::new(script.clone(), new_player(setup.light, settings));
+    let dark_human = setup.dark == PlayerKind::Human;
+    let light_human = setup.light == PlayerKind::Human;
 
     // Print commands info
     interface::commands_info();
 
     // Create a new game
-    let mut game = Game::new(&*dark, &*light);
+    let mut game = SetupGame::new(&dark, &light, setup.start);
+    for _ in 0..setup.moves.len() {
+        game.play_turn()?;
+    }
 
     // Draw the current board and game info
     interface::draw_board(game.get_current_turn());
 
+    // Hint searches are kept for the whole match, one per strength asked for, so that their tables are reused
+    let mut hint_searches: Vec<(Strength, AlphaBeta)> = Vec::new();
+
     // Proceed with turn after turn till the game ends
     while !game.is_endgame() {
         let state_side = game.get_current_state().unwrap();
@@ -106,14 +335,55 @@ fn play_game() -> Result<()> {
                         }
                         interface::draw_board(game.get_current_turn());
                     }
-                    PlayerAction::Undo => interface::draw_board(game.get_current_turn()),
+                    PlayerAction::Undo => {
+                        interface::draw_board(game.get_current_turn());
+                    }
                     PlayerAction::Other(OtherAction::Help) => {
                         interface::help();
                         interface::draw_board(game.get_current_turn());
                     }
+                    PlayerAction::Other(OtherAction::Hint(strength)) => {
+                        let turn = game.get_current_turn();
+                        let index = match hint_searches.iter().position(|&(hint_strength, _)| hint_strength == strength) {
+                            Some(index) => index,
+                            None => {
+                                hint_searches.push((strength, AlphaBeta::new(strength)));
+                                hint_searches.len() - 1
+                            }
+                        };
+                        let (coord, score) = hint_searches[index].1.best_move_and_score(turn, &settings.evaluator)?;
+                        interface::draw_board_with_hint(turn, Some(coord));
+                        interface::hint_message(state_side, coord, score);
+                    }
+                    PlayerAction::Other(OtherAction::Save(path)) => {
+                        let saved_game = SavedGame {
+                            dark: setup.dark,
+                            light: setup.light,
+                            start: setup.start,
+                            moves: game.get_moves().to_vec(),
+                        };
+                        match saved_game.save(&path) {
+                            Ok(()) => interface::game_saved_message(&path),
+                            Err(err) => interface::file_error_message(&err),
+                        }
+                    }
+                    PlayerAction::Other(OtherAction::Load(path)) => {
+                        match SavedGame::load(&path) {
+                            Ok(loaded_game) => return Ok(MatchEnd::Replaced(loaded_game)),
+                            Err(err) => interface::file_error_message(&err),
+                        }
+                    }
+                    PlayerAction::Other(OtherAction::Setup(turn)) => {
+                        return Ok(MatchEnd::Replaced(SavedGame {
+                                                         dark: setup.dark,
+                                                         light: setup.light,
+                                                         start: turn,
+                                                         moves: Vec::new(),
+                                                     }))
+                    }
                     PlayerAction::Other(OtherAction::Quit) => {
                         interface::quitting_message(game.get_current_state());
-                        return Ok(());
+                        return Ok(MatchEnd::Quit);
                     }
                 }
             }
@@ -133,5 +403,78 @@ fn play_game() -> Result<()> {
                                    Ordering::Equal => None,
                                });
 
-    Ok(())
+    let diff = game.get_current_turn().get_score_diff();
+    if !settings.post_game {
+        return Ok(MatchEnd::Over(diff));
+    }
+
+    if interface::input_transcript() {
+        let transcript = notation::transcript(&setup.start, game.get_moves()).expect("The moves were played in the game");
+        let path = interface::input_file_path();
+        match File::create(&path).and_then(|mut file| writeln!(file, "{}", transcript)) {
+            Ok(()) => interface::transcript_saved_message(&path, &transcript),
+            Err(err) => interface::file_error_message(&err),
+        }
+    }
+
+    if interface::input_review() {
+        let reviews = review::review_game(&setup.start, game.get_moves(), &AlphaBeta::new(REVIEW_STRENGTH), &settings.evaluator)?;
+        interface::review(&reviews);
+    }
+
+    Ok(MatchEnd::Over(diff))
+}
+
+/// The kind of player chosen by the user, or `None` if the user quit.
+fn player_kind(command: UserCommand) -> Option<PlayerKind> {
+    match command {
+        UserCommand::Quit => None,
+        UserCommand::HumanPlayer => Some(PlayerKind::Human),
+        UserCommand::AiWeak => Some(PlayerKind::AiWeak),
+        UserCommand::AiMedium => Some(PlayerKind::AiMedium),
+        UserCommand::AiStrong => Some(PlayerKind::AiStrong),
+        UserCommand::AiMcts => Some(PlayerKind::AiMcts),
+        _ => panic!("Returned an invalid player choice"),
+    }
+}
+
+/// Creates a player of the given kind.
+fn new_player(kind: PlayerKind, settings: &Settings) -> Box<IsPlayer<OtherAction>> {
+    let budget = mcts_player::Budget::Playouts(mcts_player::DEFAULT_PLAYOUTS);
+    let policy = mcts_player::PlayoutPolicy::CornersFirst;
+    match kind {
+        PlayerKind::Human => Box::new(human_player::HumanPlayer) as Box<IsPlayer<OtherAction>>,
+        PlayerKind::AiWeak => new_alpha_beta_player(Strength::Weak, settings),
+        PlayerKind::AiMedium => new_alpha_beta_player(Strength::Medium, settings),
+        PlayerKind::AiStrong => new_alpha_beta_player(Strength::Strong, settings),
+        PlayerKind::AiMcts => {
+            match settings.seed {
+                Some(seed) => new_ai_player(mcts_player::MctsPlayer::seeded(budget, policy, seed), settings),
+                None => new_ai_player(mcts_player::MctsPlayer::new(budget, policy), settings),
+            }
+        }
+    }
+}
+
+/// Creates an alpha-beta AI player of the given strength.
+fn new_alpha_beta_player(strength: Strength, settings: &Settings) -> Box<IsPlayer<OtherAction>> {
+    let search = match settings.seed {
+        Some(seed) => AlphaBeta::seeded(strength, seed, RANDOMNESS),
+        None => AlphaBeta::new(strength),
+    };
+    new_ai_player(AiPlayer::compose(search, settings.evaluator), settings)
+}
+
+/// Boxes an AI player, letting it play from the opening book if there is one.
+fn new_ai_player<P: IsPlayer<OtherAction> + 'static>(player: P, settings: &Settings) -> Box<IsPlayer<OtherAction>> {
+    match (&settings.book, settings.seed) {
+        (Some(book), Some(seed)) => Box::new(BookPlayer::seeded(book.clone(), player, seed)) as Box<IsPlayer<OtherAction>>,
+        (Some(book), None) => Box::new(BookPlayer::new(book.clone(), player)) as Box<IsPlayer<OtherAction>>,
+        (None, _) => Box::new(player) as Box<IsPlayer<OtherAction>>,
+    }
+}
+
+fn exit_with_error(message: &str) -> ! {
+    let _ = writeln!(io::stderr(), "{}", message);
+    process::exit(1)
 }
@@ -0,0 +1,281 @@
+//! Provides a `game::IsPlayer<::OtherAction>` type based on Monte Carlo Tree Search.
+//! Instead of evaluating positions with heuristics, it plays lots of quick games (playouts) from them
+//! and focuses its search on the moves which won the most, according to the UCT formula.
+
+use {Result, Action};
+use ai_player::{legal_moves, seeded_rng};
+use rand::{thread_rng, Rng};
+use reversi::{turn, game, Side, ReversiError};
+use reversi::board::Coord;
+use std::time::{Duration, Instant};
+
+/// Number of playouts used by the MCTS player selectable from the interface.
+pub const DEFAULT_PLAYOUTS: u32 = 5000;
+
+/// Balance between exploitation and exploration in the UCT formula.
+const EXPLORATION: f64 = ::std::f64::consts::SQRT_2;
+
+/// The corners, which guided playouts take whenever possible.
+const CORNERS: [(usize, usize); 4] = [(0, 0), (0, 7), (7, 0), (7, 7)];
+
+/// How much search an MCTS player does for each move.
+#[derive(Debug, Clone, Copy)]
+pub enum Budget {
+    /// Plays the given number of playouts.
+    Playouts(u32),
+    /// Keeps on playing playouts for the given time.
+    Time(Duration),
+}
+
+/// How moves are chosen during playouts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlayoutPolicy {
+    /// Every legal move is equally likely.
+    Random,
+    /// Corners are always taken when available, otherwise moves are random.
+    CornersFirst,
+}
+
+pub struct MctsPlayer {
+    budget: Budget,
+    policy: PlayoutPolicy,
+    /// If set, the search is deterministic (see `MctsPlayer::seeded`).
+    seed: Option<u64>,
+}
+
+impl game::IsPlayer<::OtherAction> for MctsPlayer {
+    /// Calls `find_best_move` with the player's parameters
+    fn make_move(&self, turn: &turn::Turn) -> Result<Action> {
+        let coord = match self.seed {
+            Some(seed) => MctsPlayer::find_best_move_with_rng(turn, self.budget, self.policy, &mut seeded_rng(seed, turn))?,
+            None => MctsPlayer::find_best_move(turn, self.budget, self.policy)?,
+        };
+        Ok(game::PlayerAction::Move(coord))
+    }
+}
+
+/// A node of the search tree, that is a position reached by a sequence of moves from the root.
+struct Node {
+    turn: turn::Turn,
+    /// The move leading to this node from its parent.
+    coord: Option<Coord>,
+    /// The side which played `coord`.
+    mover: Option<Side>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    /// Legal moves which have no corresponding child yet.
+    untried: Vec<Coord>,
+    visits: u32,
+    /// Sum of the results of the playouts through this node, from `mover`'s point of view.
+    wins: f64,
+}
+
+impl Node {
+    fn new<R: Rng>(turn: turn::Turn, coord: Option<Coord>, mover: Option<Side>, parent: Option<usize>, rng: &mut R) -> Node {
+        let mut untried = legal_moves(&turn);
+        rng.shuffle(&mut untried);
+        Node {
+            turn,
+            coord,
+            mover,
+            parent,
+            children: Vec::new(),
+            untried,
+            visits: 0,
+            wins: 0f64,
+        }
+    }
+
+    /// Upper confidence bound of the node's value, as given by the UCT formula.
+    fn uct(&self, parent_visits: u32) -> f64 {
+        self.wins / self.visits as f64 + EXPLORATION * ((parent_visits as f64).ln() / self.visits as f64).sqrt()
+    }
+}
+
+impl MctsPlayer {
+    pub fn new(budget: Budget, policy: PlayoutPolicy) -> MctsPlayer {
+        MctsPlayer {
+            budget,
+            policy,
+            seed: None,
+        }
+    }
+
+    /// Creates a deterministic player, so that games can be replayed exactly.
+    /// Its random choices only depend on `seed` and on the position,
+    /// so with a `Budget::Playouts` budget the same position always gets the same move.
+    pub fn seeded(budget: Budget, policy: PlayoutPolicy, seed: u64) -> MctsPlayer {
+        MctsPlayer {
+            budget,
+            policy,
+            seed: Some(seed),
+        }
+    }
+
+    /// Find the best move among the legal ones by Monte Carlo Tree Search.
+    /// The move which has been explored the most is considered the best one.
+    pub fn find_best_move(turn: &turn::Turn, budget: Budget, policy: PlayoutPolicy) -> Result<Coord> {
+        MctsPlayer::find_best_move_with_rng(turn, budget, policy, &mut thread_rng())
+    }
+
+    /// Same as `find_best_move`, but makes its random choices with `rng`.
+    pub fn find_best_move_with_rng<R: Rng>(turn: &turn::Turn, budget: Budget, policy: PlayoutPolicy, rng: &mut R) -> Result<Coord> {
+        let tree = MctsPlayer::search(turn, budget, policy, rng)?;
+        tree[0]
+            .children
+            .iter()
+            .max_by_key(|&&child| tree[child].visits)
+            .and_then(|&child| tree[child].coord)
+            .or_else(|| tree[0].untried.last().cloned())
+            .ok_or(ReversiError::EndedGame(*turn))
+    }
+
+    /// Grows the search tree from `turn` until `budget` is spent, and returns its nodes, the root first.
+    /// The root's visits count the playouts played.
+    fn search<R: Rng>(turn: &turn::Turn, budget: Budget, policy: PlayoutPolicy, rng: &mut R) -> Result<Vec<Node>> {
+        let start = Instant::now();
+
+        // If everything is alright, turn shouldn't be ended
+        turn.get_state()
+            .ok_or(ReversiError::EndedGame(*turn))?;
+
+        let mut tree = vec![Node::new(*turn, None, None, None, rng)];
+
+        // If there is only one possible move, there's no point in evaluating it.
+        if tree[0].untried.len() == 1 {
+            return Ok(tree);
+        }
+
+        let mut playouts = 0;
+        while match budget {
+                  Budget::Playouts(max_playouts) => playouts < max_playouts,
+                  Budget::Time(time) => start.elapsed() < time,
+              } {
+            // Selection: descend through fully expanded nodes
+            let mut index = 0;
+            while tree[index].untried.is_empty() && !tree[index].children.is_empty() {
+                let parent_visits = tree[index].visits;
+                index = *tree[index]
+                             .children
+                             .iter()
+                             .max_by(|&&child1, &&child2| {
+                                         tree[child1]
+                                             .uct(parent_visits)
+                                             .partial_cmp(&tree[child2].uct(parent_visits))
+                                             .expect("UCT values should be comparable")
+                                     })
+                             .expect("The node has children");
+            }
+
+            // Expansion: add a child for one of the moves not tried yet
+            if let Some(coord) = tree[index].untried.pop() {
+                let mut turn_after_move = tree[index].turn;
+                turn_after_move.make_move(coord)?;
+                let child = Node::new(turn_after_move, Some(coord), tree[index].turn.get_state(), Some(index), rng);
+                tree.push(child);
+                let child_index = tree.len() - 1;
+                tree[index].children.push(child_index);
+                index = child_index;
+            }
+
+            // Simulation: play the game out
+            let final_diff = MctsPlayer::playout(&tree[index].turn, policy, rng)?;
+
+            // Backpropagation: update the statistics of every node on the path
+            let mut current = Some(index);
+            while let Some(node_index) = current {
+                let node = &mut tree[node_index];
+                node.visits += 1;
+                node.wins += match (node.mover, final_diff) {
+                    (_, 0) => 0.5f64,
+                    (Some(Side::Light), diff) if diff > 0 => 1f64,
+                    (Some(Side::Dark), diff) if diff < 0 => 1f64,
+                    _ => 0f64,
+                };
+                current = node.parent;
+            }
+
+            playouts += 1;
+        }
+        Ok(tree)
+    }
+
+    /// Plays the game out from `turn` according to `policy`, and returns the final score difference.
+    fn playout<R: Rng>(turn: &turn::Turn, policy: PlayoutPolicy, rng: &mut R) -> Result<i16> {
+        let mut turn = *turn;
+        while turn.get_state().is_some() {
+            let moves = legal_moves(&turn);
+            let corner = match policy {
+                PlayoutPolicy::Random => None,
+                PlayoutPolicy::CornersFirst => {
+                    moves
+                        .iter()
+                        .find(|coord| CORNERS.contains(&(coord.get_row(), coord.get_col())))
+                        .cloned()
+                }
+            };
+            let coord = match corner {
+                Some(coord) => coord,
+                None => *rng.choose(&moves).expect("The game is not ended, so there are legal moves"),
+            };
+            turn.make_move(coord)?;
+        }
+        Ok(turn.get_score_diff())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Budget, MctsPlayer, PlayoutPolicy};
+    use ai_player::seeded_rng;
+    use notation;
+    use reversi::board::Coord;
+    use reversi::turn::Turn;
+    use std::time::{Duration, Instant};
+
+    /// Dark to move: a1 takes the corner and flips Light's last disks, winning at once, while c2 does not.
+    const WINNING_POSITION: &str = "--------\
+                                    -O------\
+                                    --O-----\
+                                    --XX----\
+                                    --------\
+                                    --------\
+                                    --------\
+                                    -------- X";
+
+    #[test]
+    fn playouts_budget() {
+        let turn = Turn::first_turn();
+        for &playouts in &[1, 37, 200] {
+            let tree = MctsPlayer::search(&turn, Budget::Playouts(playouts), PlayoutPolicy::Random, &mut seeded_rng(1, &turn))
+                .expect("The game is running");
+            assert_eq!(tree[0].visits, playouts);
+        }
+    }
+
+    #[test]
+    fn time_budget() {
+        let budget = Duration::from_millis(100);
+        let start = Instant::now();
+        MctsPlayer::find_best_move(&Turn::first_turn(), Budget::Time(budget), PlayoutPolicy::CornersFirst)
+            .expect("The game is running");
+        let elapsed = start.elapsed();
+        // A single playout is all the search can overrun its budget by
+        assert!(elapsed >= budget);
+        assert!(elapsed < budget + Duration::from_millis(100), "Took {:?}", elapsed);
+    }
+
+    #[test]
+    fn take_winning_corner() {
+        let turn = notation::parse_position(WINNING_POSITION).expect("The position is valid");
+        let corner = Coord::new(0, 0);
+        assert!(turn.check_move(Coord::new(1, 2)).is_ok());
+        for seed in 0..5 {
+            for &policy in &[PlayoutPolicy::Random, PlayoutPolicy::CornersFirst] {
+                let coord = MctsPlayer::find_best_move_with_rng(&turn, Budget::Playouts(200), policy, &mut seeded_rng(seed, &turn))
+                    .expect("The game is running");
+                assert_eq!(coord, corner);
+            }
+        }
+    }
+}
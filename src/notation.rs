@@ -0,0 +1,252 @@
+//! Provides conversions between moves and the standard Othello move notation,
+//! where a move is written as its column's letter followed by its row's number (e.g. `f5`)
+//! and a pass as `pa`.
+//!
+//! Positions are written as position strings, in the style of the OBF format: the 64 cells row by row,
+//! `X` for Dark disks, `O` for Light disks and `-` for empty cells, then the side to move (or `-` if the game is over),
+//! e.g. `---------------------------OX------XO--------------------------- X` for the starting position.
+
+use ai_player::legal_moves;
+use reversi::Side;
+use reversi::board::{BOARD_SIZE, Board, Cell, Coord, Disk};
+use reversi::turn::Turn;
+
+/// A pass in standard notation.
+pub const PASS: &str = "pa";
+
+/// Writes `coord` in standard notation, e.g. `f5`.
+pub fn coord_to_string(coord: Coord) -> String {
+    format!("{}{}", (b'a' + coord.get_col() as u8) as char, coord.get_row() + 1)
+}
+
+/// Reads a move in standard notation, either lowercase or uppercase.
+/// Returns `None` if `string` is not a valid move.
+pub fn parse_coord(string: &str) -> Option<Coord> {
+    let string = string.trim().to_lowercase();
+    let mut chars = string.chars();
+    match (chars.next(), chars.next(), chars.next()) {
+        (Some(col), Some(row), None) => {
+            let col = col as usize;
+            let row = row as usize;
+            if col >= 'a' as usize && col < 'a' as usize + BOARD_SIZE && row >= '1' as usize && row < '1' as usize + BOARD_SIZE {
+                Some(Coord::new(row - '1' as usize, col - 'a' as usize))
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Writes a sequence of moves in compact notation, e.g. `f5d6c3d3`.
+pub fn moves_to_string(moves: &[Coord]) -> String {
+    moves.iter().map(|&coord| coord_to_string(coord)).collect()
+}
+
+/// Writes the transcript of a game from the position of `start` in compact notation, with the passes, e.g. `f5d6c3d3`.
+/// Returns `None` if any move is illegal.
+pub fn transcript(start: &Turn, moves: &[Coord]) -> Option<String> {
+    let mut transcript = String::with_capacity(2 * moves.len());
+    let mut turn = *start;
+    for &coord in moves {
+        let side = turn.get_state()?;
+        if turn.make_move(coord).is_err() {
+            return None;
+        }
+        transcript.push_str(&coord_to_string(coord));
+        // The opponent passes if the same side is still to move
+        if turn.get_state() == Some(side) {
+            transcript.push_str(PASS);
+        }
+    }
+    Some(transcript)
+}
+
+/// Plays `moves` from the position of `start`, returning `None` if any of them is illegal.
+pub fn replay(start: &Turn, moves: &[Coord]) -> Option<Turn> {
+    let mut turn = *start;
+    for &coord in moves {
+        if turn.make_move(coord).is_err() {
+            return None;
+        }
+    }
+    Some(turn)
+}
+
+/// Reads a sequence of moves in compact notation, e.g. `f5d6c3d3`.
+/// Whitespace between moves is allowed, and passes are skipped. Returns `None` if any move is not valid.
+pub fn parse_moves(string: &str) -> Option<Vec<Coord>> {
+    let chars: Vec<char> = string.chars().filter(|c| !c.is_whitespace()).collect();
+    if !chars.len().is_multiple_of(2) {
+        return None;
+    }
+    chars.chunks(2)
+        .map(|pair| pair.iter().cloned().collect::<String>())
+        .filter(|pair| pair.to_lowercase() != PASS)
+        .map(|pair| parse_coord(&pair))
+        .collect()
+}
+
+/// Writes the position of `turn` as a position string.
+pub fn position_to_string(turn: &Turn) -> String {
+    let mut string = String::with_capacity(BOARD_SIZE * BOARD_SIZE + 2);
+    for row in 0..BOARD_SIZE {
+        for col in 0..BOARD_SIZE {
+            string.push(match *turn.get_cell(Coord::new(row, col)).expect("Coordinates are within the board") {
+                            Some(disk) if disk.get_side() == Side::Dark => 'X',
+                            Some(_) => 'O',
+                            None => '-',
+                        });
+        }
+    }
+    string.push(' ');
+    string.push(match turn.get_state() {
+                    Some(Side::Dark) => 'X',
+                    Some(Side::Light) => 'O',
+                    None => '-',
+                });
+    string
+}
+
+/// Reads a position string. Whitespace is ignored, and so is anything after a `;` (such as OBF's move lists).
+/// Dark disks can also be written `*` and empty cells `.`, and letters can be lowercase.
+/// If the side to move has no legal moves it passes, as it would in a game.
+/// Returns `None` if `string` is not a valid position, or if the game is said to be over while it is not.
+pub fn parse_position(string: &str) -> Option<Turn> {
+    let chars: Vec<char> = string.split(';')
+        .next()
+        .unwrap_or("")
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    if chars.len() != BOARD_SIZE * BOARD_SIZE + 1 {
+        return None;
+    }
+
+    let mut cells: [[Cell; BOARD_SIZE]; BOARD_SIZE] = [[None; BOARD_SIZE]; BOARD_SIZE];
+    for (index, &c) in chars[..BOARD_SIZE * BOARD_SIZE].iter().enumerate() {
+        cells[index / BOARD_SIZE][index % BOARD_SIZE] = match c {
+            'X' | 'x' | '*' => Some(Disk::new(Side::Dark)),
+            'O' | 'o' => Some(Disk::new(Side::Light)),
+            '-' | '.' => None,
+            _ => return None,
+        };
+    }
+    let board = Board::new(&cells);
+    let side = match chars[BOARD_SIZE * BOARD_SIZE] {
+        'X' | 'x' | '*' => Some(Side::Dark),
+        'O' | 'o' => Some(Side::Light),
+        '-' | '.' => None,
+        _ => return None,
+    };
+
+    // The side to move passes if it has no legal moves, as it would in a game
+    let first = side.unwrap_or(Side::Dark);
+    let state = [first, first.opposite()]
+        .iter()
+        .cloned()
+        .find(|&side| has_moves(&Turn::new(&board, Some(side))));
+    match (side, state) {
+        // A game said to be over cannot have legal moves left
+        (None, Some(_)) => None,
+        _ => Some(Turn::new(&board, state)),
+    }
+}
+
+/// Whether the side to move in `turn` has any legal move.
+fn has_moves(turn: &Turn) -> bool {
+    !legal_moves(turn).is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{coord_to_string, moves_to_string, parse_coord, parse_moves, parse_position, position_to_string, replay, transcript};
+    use reversi::Side;
+    use reversi::board::Coord;
+    use reversi::turn::Turn;
+
+    /// Dark to move; Light has to pass once during `PASS_MOVES`.
+    const PASS_POSITION: &str = "-X-------O-------O------XXO------------------------------------- X";
+    const PASS_MOVES: &str = "c2a2d4d1d3d2e4e2";
+
+    #[test]
+    fn coords() {
+        assert_eq!(coord_to_string(Coord::new(4, 5)), "f5");
+        assert_eq!(parse_coord("F5"), Some(Coord::new(4, 5)));
+        assert_eq!(parse_coord(" a1 "), Some(Coord::new(0, 0)));
+        for string in &["", "a", "a0", "a9", "i1", "f5a", "5f"] {
+            assert_eq!(parse_coord(string), None, "Accepted {:?}", string);
+        }
+    }
+
+    #[test]
+    fn parse_and_write_moves() {
+        let moves = parse_moves("f5 d6C3pa d3").expect("The moves are valid");
+        assert_eq!(moves_to_string(&moves), "f5d6c3d3");
+        assert_eq!(parse_moves(""), Some(Vec::new()));
+        for string in &["f5d", "f5z9", "f5 d6 x", "f5-d6", "papa1"] {
+            assert_eq!(parse_moves(string), None, "Accepted {:?}", string);
+        }
+    }
+
+    #[test]
+    fn transcript_with_passes() {
+        let start = parse_position(PASS_POSITION).expect("The position is valid");
+        let moves = parse_moves(PASS_MOVES).expect("The moves are valid");
+        let written = transcript(&start, &moves).expect("The moves are legal");
+        assert_eq!(written.matches("pa").count(), 1);
+        assert_eq!(written.replace("pa", ""), PASS_MOVES);
+        // Passes are skipped when reading the transcript back
+        assert_eq!(parse_moves(&written).as_ref(), Some(&moves));
+
+        assert_eq!(transcript(&Turn::first_turn(), &parse_moves("f5d6").expect("The moves are valid")),
+                   Some("f5d6".to_string()));
+        // The first move is illegal
+        assert_eq!(transcript(&Turn::first_turn(), &parse_moves("a1").expect("The move is valid")), None);
+        assert!(replay(&Turn::first_turn(), &parse_moves("a1").expect("The move is valid")).is_none());
+        assert!(replay(&start, &moves).expect("The moves are legal").get_state().is_some());
+    }
+
+    #[test]
+    fn position_round_trip() {
+        let start = "---------------------------OX------XO--------------------------- X";
+        assert_eq!(position_to_string(&Turn::first_turn()), start);
+        let turn = parse_position(PASS_POSITION).expect("The position is valid");
+        assert_eq!(position_to_string(&turn), PASS_POSITION);
+        let turn = replay(&turn, &parse_moves(PASS_MOVES).expect("The moves are valid")).expect("The moves are legal");
+        let string = position_to_string(&turn);
+        assert_eq!(parse_position(&string).map(|turn| position_to_string(&turn)), Some(string));
+
+        // Other spellings, whitespace and OBF move lists are accepted
+        let spelled = "........ ........ ........ ...ox... ...*o... ........ ........ ........ x; f5 -4";
+        assert_eq!(parse_position(spelled).map(|turn| position_to_string(&turn)), Some(start.to_string()));
+    }
+
+    #[test]
+    fn position_passes_automatically() {
+        // Dark cannot flank Light's corner disk, while Light can play c1
+        let turn = parse_position(&format!("OX{} X", "-".repeat(62))).expect("The position is valid");
+        assert_eq!(turn.get_state(), Some(Side::Light));
+        // Neither side can move: the game is over, whatever side is given
+        let turn = parse_position(&format!("OO{} X", "-".repeat(62))).expect("The position is valid");
+        assert_eq!(turn.get_state(), None);
+        assert!(parse_position(&format!("OO{} -", "-".repeat(62))).is_some());
+    }
+
+    #[test]
+    fn reject_invalid_positions() {
+        let start = "---------------------------OX------XO---------------------------";
+        for string in &[// The game is said to be over while moves are left
+                        format!("{} -", start),
+                        // Wrong length
+                        format!("{} X", &start[1..]),
+                        format!("{}- X", start),
+                        start.to_string(),
+                        String::new(),
+                        // Illegal characters
+                        format!("{} Z", start),
+                        format!("{}#{} X", &start[..10], &start[11..])] {
+            assert!(parse_position(string).is_none(), "Accepted {:?}", string);
+        }
+    }
+}
@@ -0,0 +1,412 @@
+//! Provides opening books, which let AI players play well-known moves at the start of the game.
+//!
+//! A book file is a text file with one line per move sequence, in the format
+//! `<moves> <games> <wins> <draws>`, e.g. `f5d6c3 120 64 3`.
+//! The moves are written in compact notation and the statistics refer to the recorded games
+//! through the position they lead to, with `wins` counted for the side which played the last move.
+//! Empty lines and lines starting with `#` are ignored.
+
+use {Result, Action};
+use notation;
+use rand::{thread_rng, Rng};
+use reversi::{turn, game, Side};
+use reversi::board::Coord;
+use reversi::turn::Turn;
+use ai_player::{legal_moves, seeded_rng};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Default number of moves from the start of the game recorded when learning a book.
+pub const DEFAULT_BOOK_DEPTH: usize = 12;
+
+/// Statistics of the recorded games going through a position.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BookStats {
+    pub games: u32,
+    /// Games won by the side which moved into the position.
+    pub wins: u32,
+    pub draws: u32,
+}
+
+impl BookStats {
+    /// Points scored by the side which moved into the position, counting draws as half a win.
+    pub fn points(&self) -> f64 {
+        self.wins as f64 + self.draws as f64 / 2f64
+    }
+}
+
+struct BookEntry {
+    /// A sequence of moves leading to the position.
+    moves: Vec<Coord>,
+    stats: BookStats,
+}
+
+/// A collection of positions reachable from the start of the game, with the statistics of the games going through them.
+/// Positions are identified by their board and side to move, so transpositions are recognized.
+pub struct OpeningBook {
+    entries: HashMap<String, BookEntry>,
+}
+
+impl Default for OpeningBook {
+    fn default() -> OpeningBook {
+        OpeningBook::new()
+    }
+}
+
+impl OpeningBook {
+    /// Creates an empty book.
+    pub fn new() -> OpeningBook {
+        OpeningBook { entries: HashMap::new() }
+    }
+
+    /// Loads a book from the file at `path`.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<OpeningBook> {
+        OpeningBook::read(BufReader::new(File::open(path)?))
+    }
+
+    /// Reads a book in the book file format.
+    /// Lines leading to the same position are merged, so that the position gets the games of all of them.
+    pub fn read<R: BufRead>(reader: R) -> io::Result<OpeningBook> {
+        let mut book = OpeningBook::new();
+        for line in reader.lines() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() != 4 {
+                return Err(invalid_data(line));
+            }
+            let moves = notation::parse_moves(fields[0]).ok_or_else(|| invalid_data(line))?;
+            let mut numbers = fields[1..].iter().map(|field| field.parse::<u32>());
+            let stats = match (numbers.next(), numbers.next(), numbers.next()) {
+                (Some(Ok(games)), Some(Ok(wins)), Some(Ok(draws))) => {
+                    BookStats {
+                        games,
+                        wins,
+                        draws,
+                    }
+                }
+                _ => return Err(invalid_data(line)),
+            };
+            let turn = notation::replay(&Turn::first_turn(), &moves).ok_or_else(|| invalid_data(line))?;
+            // Lines transposing to the same position add up their statistics
+            let entry = book.entries
+                .entry(position_key(&turn))
+                .or_insert_with(|| {
+                                    BookEntry {
+                                        moves,
+                                        stats: BookStats::default(),
+                                    }
+                                });
+            entry.stats.games += stats.games;
+            entry.stats.wins += stats.wins;
+            entry.stats.draws += stats.draws;
+        }
+        Ok(book)
+    }
+
+    /// Saves the book to the file at `path`.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.write(BufWriter::new(File::create(path)?))
+    }
+
+    /// Writes the book in the book file format, sorting lines by move sequence.
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let mut lines: Vec<(String, BookStats)> = self.entries
+            .values()
+            .map(|entry| (notation::moves_to_string(&entry.moves), entry.stats))
+            .collect();
+        lines.sort_by(|line1, line2| line1.0.cmp(&line2.0));
+        for (moves, stats) in lines {
+            writeln!(writer, "{} {} {} {}", moves, stats.games, stats.wins, stats.draws)?;
+        }
+        writer.flush()
+    }
+
+    /// Records the first `depth` moves of a game in the book, scored by `result`, its final disk differential
+    /// (Light's disks minus Dark's disks) as recorded, since games can end early by resignation or on time.
+    /// Without a recorded result, the game has to be complete and is scored by its final position.
+    /// Returns `false` (and leaves the book untouched) if `moves` is not a legal game, or is incomplete without a result.
+    pub fn add_game(&mut self, moves: &[Coord], result: Option<i16>, depth: usize) -> bool {
+        let final_turn = match notation::replay(&Turn::first_turn(), moves) {
+            Some(turn) => turn,
+            None => return false,
+        };
+        let final_diff = match result {
+            Some(result) => result,
+            None if final_turn.get_state().is_none() => final_turn.get_score_diff(),
+            None => return false,
+        };
+
+        let mut turn = Turn::first_turn();
+        for (index, &coord) in moves.iter().take(depth).enumerate() {
+            let mover = turn.get_state().expect("The game was replayed already");
+            turn.make_move(coord).expect("The game was replayed already");
+            let entry = self.entries
+                .entry(position_key(&turn))
+                .or_insert_with(|| {
+                                    BookEntry {
+                                        moves: moves[..index + 1].to_vec(),
+                                        stats: BookStats::default(),
+                                    }
+                                });
+            entry.stats.games += 1;
+            match (mover, final_diff) {
+                (_, 0) => entry.stats.draws += 1,
+                (Side::Light, diff) if diff > 0 => entry.stats.wins += 1,
+                (Side::Dark, diff) if diff < 0 => entry.stats.wins += 1,
+                _ => {}
+            }
+        }
+        true
+    }
+
+    /// Learns a book from a corpus of complete games, recording their first `depth` moves.
+    /// Illegal or incomplete games are skipped.
+    pub fn learn(games: &[Vec<Coord>], depth: usize) -> OpeningBook {
+        let mut book = OpeningBook::new();
+        for moves in games {
+            book.add_game(moves, None, depth);
+        }
+        book
+    }
+
+    /// Number of positions in the book.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Statistics of the position of `turn`, if it is in the book.
+    pub fn get_stats(&self, turn: &turn::Turn) -> Option<BookStats> {
+        self.entries.get(&position_key(turn)).map(|entry| entry.stats)
+    }
+
+    /// Legal moves of `turn` leading to positions in the book, together with their statistics.
+    pub fn book_moves(&self, turn: &turn::Turn) -> Vec<(Coord, BookStats)> {
+        legal_moves(turn)
+            .into_iter()
+            .filter_map(|coord| {
+                let mut turn_after_move = *turn;
+                turn_after_move
+                    .make_move(coord)
+                    .expect("The move was checked, but something went wrong!");
+                self.get_stats(&turn_after_move).map(|stats| (coord, stats))
+            })
+            .collect()
+    }
+
+    /// Chooses a book move at random, each with probability proportional to the points it scored.
+    /// Returns `None` if the position is out of book.
+    pub fn choose_move(&self, turn: &turn::Turn) -> Option<Coord> {
+        self.choose_move_with_rng(turn, &mut thread_rng())
+    }
+
+    /// Same as `choose_move`, but makes its random choice with `rng`.
+    pub fn choose_move_with_rng<R: Rng>(&self, turn: &turn::Turn, rng: &mut R) -> Option<Coord> {
+        let book_moves = self.book_moves(turn);
+        let total_points: f64 = book_moves.iter().map(|&(_, stats)| stats.points()).sum();
+        if total_points <= 0f64 {
+            return None;
+        }
+        let mut choice = rng.gen_range(0f64, total_points);
+        for &(coord, stats) in &book_moves {
+            if choice < stats.points() {
+                return Some(coord);
+            }
+            choice -= stats.points();
+        }
+        book_moves.last().map(|&(coord, _)| coord)
+    }
+}
+
+/// A player which plays from an opening book while in book, and lets another player move otherwise.
+pub struct BookPlayer<P> {
+    book: Arc<OpeningBook>,
+    player: P,
+    /// If set, book moves are chosen deterministically (see `BookPlayer::seeded`).
+    seed: Option<u64>,
+}
+
+impl<P: game::IsPlayer<::OtherAction>> BookPlayer<P> {
+    pub fn new(book: Arc<OpeningBook>, player: P) -> BookPlayer<P> {
+        BookPlayer {
+            book,
+            player,
+            seed: None,
+        }
+    }
+
+    /// Creates a player whose choice of book moves only depends on `seed` and on the position,
+    /// so that games can be replayed exactly (as long as `player` is deterministic too).
+    pub fn seeded(book: Arc<OpeningBook>, player: P, seed: u64) -> BookPlayer<P> {
+        BookPlayer {
+            book,
+            player,
+            seed: Some(seed),
+        }
+    }
+}
+
+impl<P: game::IsPlayer<::OtherAction>> game::IsPlayer<::OtherAction> for BookPlayer<P> {
+    /// Plays a book move if there is one, otherwise calls the underlying player.
+    fn make_move(&self, turn: &turn::Turn) -> Result<Action> {
+        let book_move = match self.seed {
+            Some(seed) => self.book.choose_move_with_rng(turn, &mut seeded_rng(seed, turn)),
+            None => self.book.choose_move(turn),
+        };
+        match book_move {
+            Some(coord) => Ok(game::PlayerAction::Move(coord)),
+            None => self.player.make_move(turn),
+        }
+    }
+}
+
+/// A string identifying the position of `turn`: its position string.
+fn position_key(turn: &turn::Turn) -> String {
+    notation::position_to_string(turn)
+}
+
+fn invalid_data(line: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("Invalid book line: {}", line))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OpeningBook;
+    use ai_player::{legal_moves, seeded_rng};
+    use notation;
+    use reversi::board::Coord;
+    use reversi::turn::Turn;
+
+    /// A complete game, where each side always plays its first legal move.
+    fn first_moves_game() -> Vec<Coord> {
+        let mut turn = Turn::first_turn();
+        let mut moves = Vec::new();
+        while turn.get_state().is_some() {
+            let coord = legal_moves(&turn)[0];
+            turn.make_move(coord).expect("The move is legal");
+            moves.push(coord);
+        }
+        moves
+    }
+
+    fn turn_after(moves: &str) -> Turn {
+        notation::replay(&Turn::first_turn(), &notation::parse_moves(moves).expect("The moves are valid")).expect("The moves are legal")
+    }
+
+    #[test]
+    fn read_book() {
+        let book = OpeningBook::read("# A small book\n\nf5 10 6 1\nf5d6 4 1 0\n".as_bytes()).expect("The book is valid");
+        assert_eq!(book.len(), 2);
+        let stats = book.get_stats(&turn_after("f5")).expect("f5 is in the book");
+        assert_eq!((stats.games, stats.wins, stats.draws), (10, 6, 1));
+        assert_eq!(stats.points(), 6.5f64);
+        assert!(book.get_stats(&turn_after("f5f6")).is_none());
+    }
+
+    #[test]
+    fn read_book_merges_transpositions() {
+        let book = OpeningBook::read("f5f6e6 3 2 0\ne6f6f5 5 1 1\n".as_bytes()).expect("The book is valid");
+        assert_eq!(book.len(), 1);
+        let stats = book.get_stats(&turn_after("e6f6f5")).expect("The position is in the book");
+        assert_eq!((stats.games, stats.wins, stats.draws), (8, 3, 1));
+    }
+
+    #[test]
+    fn reject_invalid_lines() {
+        for line in &["f5 10 6", "f5 10 6 1 0", "f5 ten 6 1", "f5f5 10 6 1", "z9 10 6 1"] {
+            assert!(OpeningBook::read(line.as_bytes()).is_err(), "Accepted {}", line);
+        }
+    }
+
+    #[test]
+    fn write_and_read_book() {
+        let book = OpeningBook::read("f5d6 4 1 0\nf5 10 6 1\n".as_bytes()).expect("The book is valid");
+        let mut written = Vec::new();
+        book.write(&mut written).expect("The book can be written");
+        assert_eq!(String::from_utf8(written.clone()).expect("The book is text"), "f5 10 6 1\nf5d6 4 1 0\n");
+        let book = OpeningBook::read(&written[..]).expect("The written book is valid");
+        assert_eq!(book.len(), 2);
+    }
+
+    #[test]
+    fn choose_move_by_points() {
+        // d3 never scored, so f5 is always chosen
+        let book = OpeningBook::read("f5 10 6 0\nd3 10 0 0\n".as_bytes()).expect("The book is valid");
+        let start = Turn::first_turn();
+        assert_eq!(book.book_moves(&start).len(), 2);
+        for _ in 0..20 {
+            assert_eq!(book.choose_move(&start), notation::parse_coord("f5"));
+        }
+        // Out of book, or in book with no points at all
+        assert!(book.choose_move(&turn_after("f5")).is_none());
+        let book = OpeningBook::read("f5 10 0 0\n".as_bytes()).expect("The book is valid");
+        assert!(book.choose_move(&start).is_none());
+    }
+
+    #[test]
+    fn seeded_choices_are_repeatable() {
+        let book = OpeningBook::read("f5 10 5 0
+d3 10 5 0
+c4 10 5 0
+e6 10 5 0
+".as_bytes()).expect("The book is valid");
+        let start = Turn::first_turn();
+        for seed in 0..10 {
+            let choice = book.choose_move_with_rng(&start, &mut seeded_rng(seed, &start));
+            assert!(choice.is_some());
+            assert_eq!(book.choose_move_with_rng(&start, &mut seeded_rng(seed, &start)), choice);
+        }
+    }
+
+    #[test]
+    fn learn_book_from_games() {
+        let game = first_moves_game();
+        let final_diff = notation::replay(&Turn::first_turn(), &game).expect("The game is legal").get_score_diff();
+        let book = OpeningBook::learn(&[game.clone(), game.clone(), game[..10].to_vec()], 3);
+        // The incomplete game is skipped
+        assert_eq!(book.len(), 3);
+        for length in 1..4 {
+            let turn = notation::replay(&Turn::first_turn(), &game[..length]).expect("The game is legal");
+            let stats = book.get_stats(&turn).expect("The position was learnt");
+            assert_eq!(stats.games, 2);
+            // The first move is Dark's, and wins are counted for the side which moved
+            let mover_won = if length % 2 == 1 { final_diff < 0 } else { final_diff > 0 };
+            assert_eq!(stats.wins, if mover_won { 2 } else { 0 });
+            assert_eq!(stats.draws, if final_diff == 0 { 2 } else { 0 });
+        }
+        let mut book = book;
+        assert!(!book.add_game(&game[..10], None, 3));
+        assert!(book.get_stats(&notation::replay(&Turn::first_turn(), &game[..4]).expect("The game is legal")).is_none());
+    }
+
+    #[test]
+    fn add_games_with_recorded_results() {
+        let game = first_moves_game();
+        let mut book = OpeningBook::new();
+        // A game resigned by Light after ten moves counts as a win for Dark
+        assert!(book.add_game(&game[..10], Some(-20), 2));
+        // The recorded result prevails over the final position of a complete game
+        assert!(book.add_game(&game, Some(0), 2));
+        let mut illegal = game[..4].to_vec();
+        illegal.push(illegal[0]);
+        assert!(!book.add_game(&illegal, Some(0), 2));
+
+        let after_first_move = notation::replay(&Turn::first_turn(), &game[..1]).expect("The game is legal");
+        let stats = book.get_stats(&after_first_move).expect("The position was learnt");
+        assert_eq!((stats.games, stats.wins, stats.draws), (2, 1, 1));
+        let after_second_move = notation::replay(&Turn::first_turn(), &game[..2]).expect("The game is legal");
+        let stats = book.get_stats(&after_second_move).expect("The position was learnt");
+        assert_eq!((stats.games, stats.wins, stats.draws), (2, 0, 1));
+        assert_eq!(book.len(), 2);
+    }
+}
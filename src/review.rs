@@ -0,0 +1,193 @@
+//! Provides post-game review: every move of a game is compared with the AI's analysis of the position,
+//! and the moves which lost too much evaluation are judged as inaccuracies, mistakes or blunders.
+
+use ai_player::{AlphaBeta, Score};
+use evaluation::Evaluate;
+use reversi::{turn, Side};
+use reversi::board::Coord;
+use std::f64;
+use Result;
+
+/// Evaluation lost by an inaccuracy, at least.
+pub const INACCURACY_LOSS: f64 = 10f64;
+/// Evaluation lost by a mistake, at least.
+pub const MISTAKE_LOSS: f64 = 25f64;
+/// Evaluation lost by a blunder, at least.
+pub const BLUNDER_LOSS: f64 = 50f64;
+
+/// How bad a move is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Judgement {
+    Inaccuracy,
+    Mistake,
+    Blunder,
+}
+
+/// The review of a single move.
+#[derive(Debug, Clone, Copy)]
+pub struct MoveReview {
+    /// The side which played the move.
+    pub side: Side,
+    pub coord: Coord,
+    pub score: Score,
+    /// The move the AI prefers, with its score.
+    pub best_move: Coord,
+    pub best_score: Score,
+    /// Evaluation lost by playing `coord` instead of `best_move`, from the point of view of `side`:
+    /// infinite if the move gives up a won or drawn game (see `loss`).
+    pub loss: f64,
+    /// `None` if the move is good enough.
+    pub judgement: Option<Judgement>,
+}
+
+/// Reviews the game made of `moves` from the position of `start`, analyzing each position with `search` and `evaluator`.
+/// Moves after an illegal one, or after the end of the game, are not reviewed.
+pub fn review_game(start: &turn::Turn, moves: &[Coord], search: &AlphaBeta, evaluator: &Evaluate) -> Result<Vec<MoveReview>> {
+    let mut reviews = Vec::with_capacity(moves.len());
+    let mut turn = *start;
+    for &coord in moves {
+        let side = match turn.get_state() {
+            Some(side) => side,
+            None => break,
+        };
+        if turn.check_move(coord).is_err() {
+            break;
+        }
+        let analysis = search.analyze(&turn, evaluator)?;
+        let best = &analysis.moves[0];
+        let score = analysis.moves
+            .iter()
+            .find(|move_analysis| move_analysis.coord == coord)
+            .expect("Every legal move is analyzed")
+            .score;
+        let loss = match side {
+            Side::Light => loss(best.score, score),
+            Side::Dark => loss(negate(best.score), negate(score)),
+        };
+        reviews.push(MoveReview {
+                         side,
+                         coord,
+                         score,
+                         best_move: best.coord,
+                         best_score: best.score,
+                         loss,
+                         judgement: judge(loss),
+                     });
+        turn.make_move(coord)?;
+    }
+    Ok(reviews)
+}
+
+/// Judges a move by the evaluation it lost.
+pub fn judge(loss: f64) -> Option<Judgement> {
+    if loss >= BLUNDER_LOSS {
+        Some(Judgement::Blunder)
+    } else if loss >= MISTAKE_LOSS {
+        Some(Judgement::Mistake)
+    } else if loss >= INACCURACY_LOSS {
+        Some(Judgement::Inaccuracy)
+    } else {
+        None
+    }
+}
+
+/// Evaluation lost by playing a move scored `played` instead of one scored `best`, from the point of view of Light.
+/// Evaluations and final results are compared as `Score`s rather than on a common scale, which evaluations
+/// with large weights could exceed: trading a win for an evaluation, or an evaluation for a loss, loses everything,
+/// while a draw is worth an evaluation of zero.
+fn loss(best: Score, played: Score) -> f64 {
+    match (best, played) {
+        (Score::Running(best), Score::Running(played)) => best - played,
+        (Score::Ended(best), Score::Ended(played)) if best.signum() == played.signum() => (best - played) as f64,
+        (Score::Ended(0), Score::Running(played)) => -played,
+        (Score::Running(best), Score::Ended(0)) => best,
+        _ if played < best => f64::INFINITY,
+        _ => f64::NEG_INFINITY,
+    }
+}
+
+/// The same score from the point of view of Dark.
+fn negate(score: Score) -> Score {
+    match score {
+        Score::Running(val) => Score::Running(-val),
+        Score::Ended(diff) => Score::Ended(-diff),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{judge, loss, review_game, Judgement, BLUNDER_LOSS, INACCURACY_LOSS, MISTAKE_LOSS};
+    use ai_player::{AlphaBeta, Score, Strength};
+    use evaluation::Evaluator;
+    use notation;
+    use reversi::Side;
+    use std::f64;
+
+    /// Dark to move: b2 lets Light take a1, while d6 and f4 are quiet.
+    const CORNER_POSITION: &str = "--------\
+                                   --------\
+                                   --O-----\
+                                   ---XO---\
+                                   ---OO---\
+                                   --------\
+                                   --------\
+                                           -------- X";
+
+    #[test]
+    fn judge_thresholds() {
+        assert_eq!(judge(0f64), None);
+        assert_eq!(judge(INACCURACY_LOSS - 0.1), None);
+        assert_eq!(judge(INACCURACY_LOSS), Some(Judgement::Inaccuracy));
+        assert_eq!(judge(MISTAKE_LOSS - 0.1), Some(Judgement::Inaccuracy));
+        assert_eq!(judge(MISTAKE_LOSS), Some(Judgement::Mistake));
+        assert_eq!(judge(BLUNDER_LOSS - 0.1), Some(Judgement::Mistake));
+        assert_eq!(judge(BLUNDER_LOSS), Some(Judgement::Blunder));
+        assert_eq!(judge(f64::INFINITY), Some(Judgement::Blunder));
+        assert_eq!(judge(f64::NEG_INFINITY), None);
+    }
+
+    #[test]
+    fn results_beat_any_evaluation() {
+        assert_eq!(loss(Score::Running(30f64), Score::Running(-20f64)), 50f64);
+        assert_eq!(loss(Score::Ended(10), Score::Ended(2)), 8f64);
+        // Evaluations with large weights still never outweigh a win or a loss
+        assert_eq!(loss(Score::Ended(2), Score::Running(1e6)), f64::INFINITY);
+        assert_eq!(loss(Score::Running(-1e6), Score::Ended(-2)), f64::INFINITY);
+        assert_eq!(loss(Score::Ended(2), Score::Ended(0)), f64::INFINITY);
+        assert_eq!(loss(Score::Running(1e6), Score::Ended(2)), f64::NEG_INFINITY);
+        // A draw is worth an evaluation of zero
+        assert_eq!(loss(Score::Ended(0), Score::Running(-5f64)), 5f64);
+        assert_eq!(loss(Score::Running(5f64), Score::Ended(0)), 5f64);
+    }
+
+    #[test]
+    fn giving_up_a_corner_is_a_blunder() {
+        let start = notation::parse_position(CORNER_POSITION).expect("The position is valid");
+        let search = AlphaBeta::new(Strength::Medium);
+        let evaluator = Evaluator::default();
+
+        let blunder = notation::parse_moves("b2").expect("The move is valid");
+        let reviews = review_game(&start, &blunder, &search, &evaluator).expect("The review succeeds");
+        assert_eq!(reviews.len(), 1);
+        assert_eq!(reviews[0].side, Side::Dark);
+        assert_eq!(reviews[0].judgement, Some(Judgement::Blunder));
+        // The preferred alternative is given with its score, which the played move falls short of for Dark
+        assert!(reviews[0].best_move != reviews[0].coord);
+        assert!(reviews[0].best_score < reviews[0].score);
+        assert!(reviews[0].loss >= BLUNDER_LOSS);
+
+        let good = [reviews[0].best_move];
+        let reviews = review_game(&start, &good, &search, &evaluator).expect("The review succeeds");
+        assert_eq!(reviews[0].judgement, None);
+        assert_eq!(reviews[0].loss, 0f64);
+    }
+
+    #[test]
+    fn reviews_stop_at_illegal_moves() {
+        let start = notation::parse_position(CORNER_POSITION).expect("The position is valid");
+        let moves = notation::parse_moves("d6a1").expect("The moves are valid");
+        let reviews = review_game(&start, &moves, &AlphaBeta::new(Strength::Weak), &Evaluator::default())
+            .expect("The review succeeds");
+        assert_eq!(reviews.len(), 1);
+    }
+}
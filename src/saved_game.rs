@@ -0,0 +1,308 @@
+//! Provides saving and loading of games in progress.
+//!
+//! A saved game is a text file with one `key = value` line for each of the players and the moves, e.g.
+//!
+//! ```text
+//! dark = human
+//! light = medium
+//! moves = f5d6c3d3
+//! ```
+//!
+//! Players are `human`, `weak`, `medium`, `strong` or `mcts`, and moves are written in compact notation.
+//! Games started from a position other than the starting one have a `position = <position string>` line too
+//! (see the `notation` module). Empty lines and lines starting with `#` are ignored.
+//!
+//! Files with the `ggf` extension are in GGF instead (see the `ggf` module), with the players' kinds as their names:
+//! players with any other name are loaded as human players.
+
+use {Result, Action};
+use ggf::{self, GgfGame};
+use notation;
+use reversi::{turn, game};
+use reversi::board::Coord;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::rc::Rc;
+
+/// The kinds of players a game can be played by.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlayerKind {
+    Human,
+    AiWeak,
+    AiMedium,
+    AiStrong,
+    AiMcts,
+}
+
+impl PlayerKind {
+    /// The name of the kind of player in saved games.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            PlayerKind::Human => "human",
+            PlayerKind::AiWeak => "weak",
+            PlayerKind::AiMedium => "medium",
+            PlayerKind::AiStrong => "strong",
+            PlayerKind::AiMcts => "mcts",
+        }
+    }
+
+    /// The kind of player called `name` in saved games.
+    pub fn from_name(name: &str) -> Option<PlayerKind> {
+        match name {
+            "human" => Some(PlayerKind::Human),
+            "weak" => Some(PlayerKind::AiWeak),
+            "medium" => Some(PlayerKind::AiMedium),
+            "strong" => Some(PlayerKind::AiStrong),
+            "mcts" => Some(PlayerKind::AiMcts),
+            _ => None,
+        }
+    }
+}
+
+/// A game in progress: who plays it, the position it started from and the moves played so far.
+#[derive(Debug, Clone)]
+pub struct SavedGame {
+    pub dark: PlayerKind,
+    pub light: PlayerKind,
+    pub start: turn::Turn,
+    pub moves: Vec<Coord>,
+}
+
+impl SavedGame {
+    /// Loads a game from the file at `path`, in GGF if its extension is `ggf`.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<SavedGame> {
+        if is_ggf(path.as_ref()) {
+            let ggf_game = ggf::load_games(path)?
+                .into_iter()
+                .next()
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "No game in GGF file"))?;
+            Ok(SavedGame::from_ggf(&ggf_game))
+        } else {
+            SavedGame::read(BufReader::new(File::open(path)?))
+        }
+    }
+
+    /// Reads a game in the saved game format, checking that its moves are legal.
+    pub fn read<R: BufRead>(reader: R) -> io::Result<SavedGame> {
+        let (mut dark, mut light, mut start, mut moves) = (None, None, turn::Turn::first_turn(), Vec::new());
+        for line in reader.lines() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.splitn(2, '=');
+            match (fields.next().map(str::trim), fields.next().map(str::trim)) {
+                (Some("dark"), Some(name)) => dark = Some(PlayerKind::from_name(name).ok_or_else(|| invalid_data(line))?),
+                (Some("light"), Some(name)) => light = Some(PlayerKind::from_name(name).ok_or_else(|| invalid_data(line))?),
+                (Some("position"), Some(string)) => start = notation::parse_position(string).ok_or_else(|| invalid_data(line))?,
+                (Some("moves"), Some(string)) => moves = notation::parse_moves(string).ok_or_else(|| invalid_data(line))?,
+                _ => return Err(invalid_data(line)),
+            }
+        }
+
+        let mut turn = start;
+        for &coord in &moves {
+            turn.make_move(coord)
+                .map_err(|_| {
+                             io::Error::new(io::ErrorKind::InvalidData,
+                                            format!("Illegal move in saved game: {}", notation::coord_to_string(coord)))
+                         })?;
+        }
+
+        match (dark, light) {
+            (Some(dark), Some(light)) => {
+                Ok(SavedGame {
+                       dark,
+                       light,
+                       start,
+                       moves,
+                   })
+            }
+            _ => Err(io::Error::new(io::ErrorKind::InvalidData, "Missing players in saved game")),
+        }
+    }
+
+    /// Saves the game to the file at `path`, in GGF if its extension is `ggf`.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        if is_ggf(path.as_ref()) {
+            self.to_ggf().save(path)
+        } else {
+            self.write(BufWriter::new(File::create(path)?))
+        }
+    }
+
+    /// Writes the game in the saved game format.
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "dark = {}", self.dark.name())?;
+        writeln!(writer, "light = {}", self.light.name())?;
+        let position = notation::position_to_string(&self.start);
+        if position != notation::position_to_string(&turn::Turn::first_turn()) {
+            writeln!(writer, "position = {}", position)?;
+        }
+        writeln!(writer, "moves = {}", notation::moves_to_string(&self.moves))?;
+        writer.flush()
+    }
+
+    /// The game recorded in GGF, with the players' kinds as their names.
+    pub fn to_ggf(&self) -> GgfGame {
+        GgfGame::new(self.dark.name(), self.light.name(), &self.start, &self.moves).expect("Saved games are legal")
+    }
+
+    /// The game recorded in `ggf_game`, played by the kinds of players it names, or by humans.
+    pub fn from_ggf(ggf_game: &GgfGame) -> SavedGame {
+        SavedGame {
+            dark: PlayerKind::from_name(&ggf_game.dark).unwrap_or(PlayerKind::Human),
+            light: PlayerKind::from_name(&ggf_game.light).unwrap_or(PlayerKind::Human),
+            start: ggf_game.start,
+            moves: ggf_game.moves.clone(),
+        }
+    }
+}
+
+/// A player which first plays the moves of a script, shared with its opponent, and then lets another player move.
+/// Replaying a loaded game through its players restores the game's history, so that moves can still be undone.
+pub struct ScriptedPlayer {
+    script: Rc<RefCell<VecDeque<Coord>>>,
+    player: Box<game::IsPlayer<::OtherAction>>,
+}
+
+impl ScriptedPlayer {
+    pub fn new(script: Rc<RefCell<VecDeque<Coord>>>, player: Box<game::IsPlayer<::OtherAction>>) -> ScriptedPlayer {
+        ScriptedPlayer {
+            script,
+            player,
+        }
+    }
+}
+
+impl game::IsPlayer<::OtherAction> for ScriptedPlayer {
+    /// Plays the next move of the script if there is one, otherwise calls the underlying player.
+    fn make_move(&self, turn: &turn::Turn) -> Result<Action> {
+        let scripted_move = self.script.borrow_mut().pop_front();
+        match scripted_move {
+            Some(coord) => Ok(game::PlayerAction::Move(coord)),
+            None => self.player.make_move(turn),
+        }
+    }
+}
+
+/// Whether the file at `path` is in GGF, according to its extension.
+fn is_ggf(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension == "ggf" || extension == "GGF")
+}
+
+fn invalid_data(line: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("Invalid saved game line: {}", line))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PlayerKind, SavedGame, ScriptedPlayer};
+    use {Action, Result};
+    use ggf;
+    use notation;
+    use reversi::game::{IsPlayer, PlayerAction};
+    use reversi::turn::Turn;
+    use setup_game::SetupGame;
+    use std::cell::RefCell;
+    use std::collections::VecDeque;
+    use std::rc::Rc;
+
+    /// A position from which Light has to pass once during `PASSING_MOVES`.
+    const PASSING_START: &str = "-X-------O-------O------XXO------------------------------------- X";
+    const PASSING_MOVES: &str = "c2a2d4d1d3d2e4e2";
+
+    fn passing_game() -> SavedGame {
+        SavedGame {
+            dark: PlayerKind::AiMcts,
+            light: PlayerKind::Human,
+            start: notation::parse_position(PASSING_START).expect("The position is valid"),
+            moves: notation::parse_moves(PASSING_MOVES).expect("The moves are valid"),
+        }
+    }
+
+    fn assert_same_game(game1: &SavedGame, game2: &SavedGame) {
+        assert_eq!(game1.dark, game2.dark);
+        assert_eq!(game1.light, game2.light);
+        assert_eq!(notation::position_to_string(&game1.start), notation::position_to_string(&game2.start));
+        assert_eq!(game1.moves, game2.moves);
+    }
+
+    #[test]
+    fn save_and_load_game() {
+        let game = passing_game();
+        let transcript = notation::transcript(&game.start, &game.moves).expect("The moves are legal");
+        assert!(transcript.contains(notation::PASS));
+
+        let mut written = Vec::new();
+        game.write(&mut written).expect("The game can be written");
+        let text = String::from_utf8(written.clone()).expect("Saved games are text");
+        assert!(text.contains(&format!("position = {}", PASSING_START)));
+        assert_same_game(&SavedGame::read(&written[..]).expect("The written game is valid"), &game);
+
+        let mut written = Vec::new();
+        game.to_ggf().write(&mut written).expect("The game can be written");
+        let ggf_games = ggf::read_games(&written[..]).expect("The written game is valid");
+        assert_same_game(&SavedGame::from_ggf(&ggf_games[0]), &game);
+    }
+
+    #[test]
+    fn starting_position_is_not_saved() {
+        let game = SavedGame {
+            dark: PlayerKind::Human,
+            light: PlayerKind::AiStrong,
+            start: Turn::first_turn(),
+            moves: notation::parse_moves("f5d6c3").expect("The moves are valid"),
+        };
+        let mut written = Vec::new();
+        game.write(&mut written).expect("The game can be written");
+        assert_eq!(String::from_utf8(written.clone()).expect("Saved games are text"),
+                   "dark = human\nlight = strong\nmoves = f5d6c3\n");
+        assert_same_game(&SavedGame::read(&written[..]).expect("The written game is valid"), &game);
+    }
+
+    #[test]
+    fn reject_invalid_games() {
+        for text in &["dark = human\n",
+                      "dark = human\nlight = genius\n",
+                      "dark = human\nlight = weak\nmoves = f5f5\n",
+                      "dark = human\nlight = weak\nposition = XO\n",
+                      "dark = human\nlight = weak\ncolour = red\n"] {
+            assert!(SavedGame::read(text.as_bytes()).is_err(), "Accepted {:?}", text);
+        }
+    }
+
+    /// A player which always asks to undo.
+    struct Undoer;
+
+    impl IsPlayer<::OtherAction> for Undoer {
+        fn make_move(&self, _: &Turn) -> Result<Action> {
+            Ok(PlayerAction::Undo)
+        }
+    }
+
+    #[test]
+    fn scripted_players_replay_the_game() {
+        let game = passing_game();
+        let script = Rc::new(RefCell::new(game.moves.iter().cloned().collect::<VecDeque<_>>()));
+        let dark = ScriptedPlayer::new(script.clone(), Box::new(Undoer));
+        let light = ScriptedPlayer::new(script.clone(), Box::new(Undoer));
+        let mut setup_game = SetupGame::new(&dark, &light, game.start);
+        for _ in 0..game.moves.len() {
+            setup_game.play_turn().expect("The scripted moves are legal");
+        }
+        assert!(script.borrow().is_empty());
+        assert_eq!(setup_game.get_moves(), &game.moves[..]);
+        let end = notation::replay(&game.start, &game.moves).expect("The moves are legal");
+        assert_eq!(notation::position_to_string(setup_game.get_current_turn()),
+                   notation::position_to_string(&end));
+
+        // Once the script is over, the underlying players move, and the history lets them undo
+        setup_game.play_turn().expect("There are moves to undo");
+        assert_eq!(setup_game.get_moves(), &game.moves[..game.moves.len() - 2]);
+    }
+}
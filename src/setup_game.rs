@@ -0,0 +1,79 @@
+//! Provides games which can start from any position, not only from the starting one.
+
+use {Result};
+use reversi::{ReversiError, Side};
+use reversi::board::Coord;
+use reversi::game::{IsPlayer, PlayerAction};
+use reversi::turn::{State, Turn};
+
+/// A game between two players, started from a given position.
+/// It is played like a `reversi::game::Game`, which always starts from the starting position.
+pub struct SetupGame<'a, A: 'a> {
+    dark: &'a IsPlayer<A>,
+    light: &'a IsPlayer<A>,
+    turn: Turn,
+    /// The turns before the current one, to undo moves.
+    history: Vec<Turn>,
+    /// The moves played from each turn of `history`.
+    moves: Vec<Coord>,
+}
+
+impl<'a, A: 'a> SetupGame<'a, A> {
+    /// Creates a game between `dark` and `light` starting from `turn`.
+    pub fn new(dark: &'a IsPlayer<A>, light: &'a IsPlayer<A>, turn: Turn) -> SetupGame<'a, A> {
+        SetupGame {
+            dark,
+            light,
+            turn,
+            history: Vec::new(),
+            moves: Vec::new(),
+        }
+    }
+
+    pub fn get_current_turn(&self) -> &Turn {
+        &self.turn
+    }
+
+    /// The moves played from the starting position to the current one, without those taken back.
+    pub fn get_moves(&self) -> &[Coord] {
+        &self.moves
+    }
+
+    pub fn get_current_state(&self) -> State {
+        self.turn.get_state()
+    }
+
+    pub fn is_endgame(&self) -> bool {
+        self.turn.get_state().is_none()
+    }
+
+    /// Asks the player whose turn it is for an action, and plays it.
+    /// Undo takes the game back to the last turn when the same player was to move,
+    /// and returns `ReversiError::NoUndo` if there is no such turn.
+    pub fn play_turn(&mut self) -> Result<PlayerAction<A>> {
+        let side = self.turn.get_state().ok_or(ReversiError::EndedGame(self.turn))?;
+        let action = match side {
+            Side::Dark => self.dark.make_move(&self.turn)?,
+            Side::Light => self.light.make_move(&self.turn)?,
+        };
+        match action {
+            PlayerAction::Move(coord) => {
+                let previous_turn = self.turn;
+                self.turn.make_move(coord)?;
+                self.history.push(previous_turn);
+                self.moves.push(coord);
+            }
+            PlayerAction::Undo => {
+                let position = self.history
+                    .iter()
+                    .rposition(|turn| turn.get_state() == Some(side))
+                    .ok_or(ReversiError::NoUndo)?;
+                self.turn = self.history[position];
+                self.history.truncate(position);
+                self.moves.truncate(position);
+            }
+            PlayerAction::Other(_) => {}
+        }
+        Ok(action)
+    }
+}
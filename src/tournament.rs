@@ -0,0 +1,383 @@
+//! Provides tournaments between AI engines, in round-robin or Swiss format, played headless.
+//!
+//! Each round is played in parallel unless an engine is timed (see `engine_match::play_games`),
+//! and games are scored 1 for a win, ½ for a draw and 0 for a loss.
+//! Ties in the standings are broken by the Sonneborn-Berger score
+//! (the scores of the opponents an engine beat, plus half the scores of those it drew with),
+//! then by the Buchholz score (the sum of the scores of the opponents an engine met),
+//! and finally by the disk differential.
+
+use {Result};
+use engine_match::{self, EngineConfig, GameRecord, Pairing, Summary};
+use reversi::turn::Turn;
+use std::cmp::Ordering;
+use std::io::{self, Write};
+
+/// How the engines of a tournament are paired.
+#[derive(Debug, Clone, Copy)]
+pub enum Format {
+    /// Every engine meets every other engine, once in each cycle, with colours swapped from one cycle to the next.
+    RoundRobin(u32),
+    /// Engines meet others with a similar score, for the given number of rounds, never meeting twice if possible.
+    /// With an odd number of engines, one sits out each round and scores a point.
+    Swiss(u32),
+}
+
+/// The standing of an engine in a tournament.
+#[derive(Debug, Clone, Copy)]
+pub struct Standing {
+    pub engine: usize,
+    pub points: f64,
+    pub summary: Summary,
+    pub sonneborn_berger: f64,
+    pub buchholz: f64,
+}
+
+/// A tournament between AI engines, with the games played so far.
+pub struct Tournament {
+    pub engines: Vec<EngineConfig>,
+    pub records: Vec<GameRecord>,
+    /// The engines which sat out a Swiss round, once for each round.
+    pub byes: Vec<usize>,
+}
+
+impl Tournament {
+    pub fn new(engines: Vec<EngineConfig>) -> Tournament {
+        Tournament {
+            engines,
+            records: Vec::new(),
+            byes: Vec::new(),
+        }
+    }
+
+    /// Plays all the rounds of the tournament in the given format.
+    /// If `seed` is given, game `n` of the tournament is played by players seeded with `seed + n`.
+    pub fn play(&mut self, format: Format, seed: Option<u64>) -> Result<()> {
+        match format {
+            Format::RoundRobin(cycles) => {
+                for cycle in 0..cycles {
+                    for round in round_robin_rounds(self.engines.len()) {
+                        let round = round.into_iter()
+                            .map(|(dark, light)| if cycle % 2 == 0 { (dark, light) } else { (light, dark) })
+                            .collect::<Vec<_>>();
+                        self.play_round(&round, seed)?;
+                    }
+                }
+            }
+            Format::Swiss(rounds) => {
+                for _ in 0..rounds {
+                    let round = self.swiss_round();
+                    self.play_round(&round, seed)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Plays a round of games between the given `(dark, light)` pairs of engines.
+    fn play_round(&mut self, round: &[(usize, usize)], seed: Option<u64>) -> Result<()> {
+        let played = self.records.len() as u64;
+        let pairings = round.iter()
+            .enumerate()
+            .map(|(index, &(dark, light))| {
+                     Pairing {
+                         dark,
+                         light,
+                         start: Turn::first_turn(),
+                         seed: seed.map(|seed| seed.wrapping_add(played + index as u64)),
+                     }
+                 })
+            .collect::<Vec<_>>();
+        let records = engine_match::play_games(&self.engines, &pairings)?;
+        self.records.extend(records);
+        Ok(())
+    }
+
+    /// Pairs the engines for the next Swiss round, from the top of the standings down,
+    /// each with the best placed engine it has not met yet (or with the next one, if it has met them all).
+    /// The engine to sit out is the lowest placed one which has not sat out yet.
+    fn swiss_round(&mut self) -> Vec<(usize, usize)> {
+        let mut unpaired: Vec<usize> = self.standings().iter().map(|standing| standing.engine).collect();
+        if unpaired.len() % 2 == 1 {
+            let bye = unpaired.iter()
+                .rposition(|engine| !self.byes.contains(engine))
+                .unwrap_or(unpaired.len() - 1);
+            self.byes.push(unpaired.remove(bye));
+        }
+        let mut round = Vec::with_capacity(unpaired.len() / 2);
+        while !unpaired.is_empty() {
+            let engine = unpaired.remove(0);
+            let opponent = unpaired.iter()
+                .position(|&opponent| !self.have_met(engine, opponent))
+                .unwrap_or(0);
+            let opponent = unpaired.remove(opponent);
+            // The engine which played Dark fewer times gets Dark
+            if self.dark_games(opponent) < self.dark_games(engine) {
+                round.push((opponent, engine));
+            } else {
+                round.push((engine, opponent));
+            }
+        }
+        round
+    }
+
+    fn have_met(&self, engine: usize, opponent: usize) -> bool {
+        self.records.iter().any(|record| record.disks_of(engine).is_some() && record.disks_of(opponent).is_some())
+    }
+
+    fn dark_games(&self, engine: usize) -> usize {
+        self.records.iter().filter(|record| record.dark == engine).count()
+    }
+
+    /// The score of `engine` in the tournament, counting byes as wins.
+    pub fn points(&self, engine: usize) -> f64 {
+        engine_match::summarize(&self.records, engine).points() + self.byes.iter().filter(|&&bye| bye == engine).count() as f64
+    }
+
+    /// The standings of the engines, from first to last.
+    pub fn standings(&self) -> Vec<Standing> {
+        let points: Vec<f64> = (0..self.engines.len()).map(|engine| self.points(engine)).collect();
+        let mut standings: Vec<Standing> = (0..self.engines.len())
+            .map(|engine| {
+                let (mut sonneborn_berger, mut buchholz) = (0f64, 0f64);
+                for record in &self.records {
+                    if let (Some(score), Some(opponent)) = (game_points(record, engine), opponent(record, engine)) {
+                        sonneborn_berger += score * points[opponent];
+                        buchholz += points[opponent];
+                    }
+                }
+                Standing {
+                    engine,
+                    points: points[engine],
+                    summary: engine_match::summarize(&self.records, engine),
+                    sonneborn_berger,
+                    buchholz,
+                }
+            })
+            .collect();
+        standings.sort_by(|first, second| {
+            compare(second.points, first.points)
+                .then(compare(second.sonneborn_berger, first.sonneborn_berger))
+                .then(compare(second.buchholz, first.buchholz))
+                .then(disk_diff(&second.summary).cmp(&disk_diff(&first.summary)))
+                .then(first.engine.cmp(&second.engine))
+        });
+        standings
+    }
+
+    /// Writes the crosstable of the tournament, with the engines in the order of the standings.
+    /// The cell of a row and a column is the score of the row's engine against the column's one.
+    pub fn write_crosstable<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let standings = self.standings();
+        let name_width = self.engines.iter().map(|engine| engine.name.len()).max().unwrap_or(0).max("Engine".len());
+        write!(writer, "{:>3}  {:<width$}", "#", "Engine", width = name_width)?;
+        for rank in 1..standings.len() + 1 {
+            write!(writer, " {:>5}", rank)?;
+        }
+        writeln!(writer, "  {:>6} {:>5} {:>7} {:>8} {:>6}", "Points", "Games", "S-B", "Buchholz", "Disks")?;
+        for (rank, standing) in standings.iter().enumerate() {
+            write!(writer,
+                   "{:>3}  {:<width$}",
+                   rank + 1,
+                   self.engines[standing.engine].name,
+                   width = name_width)?;
+            for other in &standings {
+                let scores: Vec<f64> = self.records
+                    .iter()
+                    .filter(|record| opponent(record, standing.engine) == Some(other.engine))
+                    .filter_map(|record| game_points(record, standing.engine))
+                    .collect();
+                if other.engine == standing.engine {
+                    write!(writer, " {:>5}", "*")?;
+                } else if scores.is_empty() {
+                    write!(writer, " {:>5}", "-")?;
+                } else {
+                    write!(writer, " {:>5}", scores.iter().sum::<f64>())?;
+                }
+            }
+            writeln!(writer,
+                     "  {:>6} {:>5} {:>7.2} {:>8} {:>+6}",
+                     standing.points,
+                     standing.summary.games(),
+                     standing.sonneborn_berger,
+                     standing.buchholz,
+                     disk_diff(&standing.summary))?;
+        }
+        writer.flush()
+    }
+}
+
+/// The rounds of a single round robin between `engines` engines, as `(dark, light)` pairs,
+/// scheduled by the circle method: the first engine stays put while the others rotate around it.
+/// With an odd number of engines, one of them sits out each round.
+pub fn round_robin_rounds(engines: usize) -> Vec<Vec<(usize, usize)>> {
+    // An odd number of engines is completed with a dummy one, whose opponent sits out
+    let size = engines + engines % 2;
+    let mut circle: Vec<usize> = (0..size).collect();
+    let mut rounds = Vec::with_capacity(size.saturating_sub(1));
+    for round in 0..size.saturating_sub(1) {
+        let mut pairs = Vec::with_capacity(size / 2);
+        for index in 0..size / 2 {
+            let (first, second) = (circle[index], circle[size - 1 - index]);
+            if first >= engines || second >= engines {
+                continue;
+            }
+            // Colours alternate for the fixed engine, and by position for the others
+            if (index == 0 && round % 2 == 1) || (index > 0 && index % 2 == 1) {
+                pairs.push((second, first));
+            } else {
+                pairs.push((first, second));
+            }
+        }
+        rounds.push(pairs);
+        let last = circle.pop().expect("There are engines in the circle");
+        circle.insert(1, last);
+    }
+    rounds
+}
+
+/// The points `engine` scored in the game of `record`, if it played it.
+fn game_points(record: &GameRecord, engine: usize) -> Option<f64> {
+    record.disks_of(engine).map(|(disks, opponent_disks)| match disks.cmp(&opponent_disks) {
+                                    Ordering::Greater => 1f64,
+                                    Ordering::Equal => 0.5f64,
+                                    Ordering::Less => 0f64,
+                                })
+}
+
+/// The opponent of `engine` in the game of `record`, if it played it.
+fn opponent(record: &GameRecord, engine: usize) -> Option<usize> {
+    if engine == record.dark {
+        Some(record.light)
+    } else if engine == record.light {
+        Some(record.dark)
+    } else {
+        None
+    }
+}
+
+fn disk_diff(summary: &Summary) -> i64 {
+    summary.disks as i64 - summary.opponent_disks as i64
+}
+
+/// Compares scores, which are never NaN.
+fn compare(first: f64, second: f64) -> Ordering {
+    first.partial_cmp(&second).unwrap_or(Ordering::Equal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{round_robin_rounds, Tournament};
+    use engine_match::{EngineConfig, GameRecord};
+    use reversi::turn::Turn;
+
+    fn tournament(engines: usize) -> Tournament {
+        Tournament::new((0..engines).map(|_| EngineConfig::parse("weak").expect("The spec is valid")).collect())
+    }
+
+    fn record(dark: usize, light: usize, dark_disks: u8, light_disks: u8) -> GameRecord {
+        GameRecord {
+            dark,
+            light,
+            start: Turn::first_turn(),
+            moves: Vec::new(),
+            dark_disks,
+            light_disks,
+        }
+    }
+
+    fn rank(tournament: &Tournament, engine: usize) -> usize {
+        tournament.standings().iter().position(|standing| standing.engine == engine).expect("The engine is in the standings")
+    }
+
+    #[test]
+    fn round_robin_pairs_every_engine_once() {
+        for engines in 2..10 {
+            let rounds = round_robin_rounds(engines);
+            assert_eq!(rounds.len(), engines + engines % 2 - 1);
+            let mut met = vec![vec![0; engines]; engines];
+            for round in &rounds {
+                let mut playing = vec![false; engines];
+                for &(dark, light) in round {
+                    assert!(!playing[dark] && !playing[light], "An engine plays twice in a round");
+                    playing[dark] = true;
+                    playing[light] = true;
+                    met[dark][light] += 1;
+                    met[light][dark] += 1;
+                }
+                // With an odd number of engines, exactly one sits out
+                assert_eq!(playing.iter().filter(|&&playing| !playing).count(), engines % 2);
+            }
+            for (engine, met_by_engine) in met.iter().enumerate() {
+                for (other, &games) in met_by_engine.iter().enumerate() {
+                    assert_eq!(games, if engine == other { 0 } else { 1 });
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn swiss_avoids_rematches() {
+        let mut tournament = tournament(4);
+        tournament.records = vec![record(0, 1, 40, 24), record(2, 3, 40, 24), record(0, 2, 40, 24), record(3, 1, 40, 24)];
+        // The standings are 0, 2, 3, 1: 0 has met 2 already, so it meets 3
+        let standings: Vec<usize> = tournament.standings().iter().map(|standing| standing.engine).collect();
+        assert_eq!(standings, vec![0, 2, 3, 1]);
+        let mut round: Vec<(usize, usize)> = tournament.swiss_round()
+            .into_iter()
+            .map(|(dark, light)| (dark.min(light), dark.max(light)))
+            .collect();
+        round.sort();
+        assert_eq!(round, vec![(0, 3), (1, 2)]);
+    }
+
+    #[test]
+    fn swiss_rotates_byes() {
+        let mut tournament = tournament(3);
+        assert_eq!(tournament.swiss_round(), vec![(0, 1)]);
+        tournament.records.push(record(0, 1, 40, 24));
+        assert_eq!(tournament.swiss_round(), vec![(2, 0)]);
+        tournament.records.push(record(2, 0, 40, 24));
+        assert_eq!(tournament.swiss_round(), vec![(1, 2)]);
+        assert_eq!(tournament.byes, vec![2, 1, 0]);
+        // Byes count as wins
+        assert_eq!((tournament.points(0), tournament.points(1), tournament.points(2)), (2f64, 1f64, 2f64));
+    }
+
+    #[test]
+    fn sonneborn_berger_breaks_ties_first() {
+        let mut tournament = tournament(6);
+        tournament.records = vec![record(0, 2, 40, 24), record(3, 0, 40, 24), record(1, 4, 40, 24), record(5, 1, 40, 24)];
+        tournament.byes = vec![2, 2, 5, 5, 5];
+        let standings = tournament.standings();
+        let (first, second) = (&standings[rank(&tournament, 0)], &standings[rank(&tournament, 1)]);
+        assert_eq!((first.points, second.points), (1f64, 1f64));
+        assert_eq!((first.sonneborn_berger, first.buchholz), (2f64, 3f64));
+        assert_eq!((second.sonneborn_berger, second.buchholz), (0f64, 4f64));
+        assert!(rank(&tournament, 0) < rank(&tournament, 1));
+    }
+
+    #[test]
+    fn buchholz_breaks_ties_before_disks() {
+        let mut tournament = tournament(4);
+        tournament.records = vec![record(0, 2, 0, 64), record(1, 3, 30, 34)];
+        tournament.byes = vec![2, 2];
+        assert!(rank(&tournament, 0) < rank(&tournament, 1));
+
+        // Without the byes, the Buchholz scores are the same and the disks decide
+        tournament.byes.clear();
+        tournament.records = vec![record(0, 2, 30, 34), record(1, 3, 0, 64)];
+        assert!(rank(&tournament, 0) < rank(&tournament, 1));
+        tournament.records = vec![record(0, 2, 0, 64), record(1, 3, 30, 34)];
+        assert!(rank(&tournament, 1) < rank(&tournament, 0));
+    }
+
+    #[test]
+    fn draws_score_half_a_point() {
+        let mut tournament = tournament(3);
+        tournament.records = vec![record(0, 1, 32, 32), record(1, 2, 40, 24)];
+        let standings = tournament.standings();
+        let standing = &standings[rank(&tournament, 0)];
+        assert_eq!((standing.points, standing.sonneborn_berger, standing.buchholz), (0.5f64, 0.75f64, 1.5f64));
+    }
+}
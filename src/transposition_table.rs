@@ -0,0 +1,244 @@
+//! Provides a Zobrist-keyed transposition table, shared by the threads searching for the AI's best move.
+
+use ai_player::Score;
+use rand::{Rng, SeedableRng, XorShiftRng};
+use reversi::Side;
+use reversi::board::{BOARD_SIZE, Coord};
+use reversi::turn::Turn;
+use std::fmt;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Default number of entries of the table used by `AlphaBeta`.
+pub const DEFAULT_SIZE: usize = 1 << 16;
+
+/// Entries are split among this many independently locked shards, to reduce contention between threads.
+const SHARDS: usize = 64;
+
+/// Fixed seed for the Zobrist keys, so that hashes are the same from one run to the next.
+const ZOBRIST_SEED: [u32; 4] = [0x5eed_0001, 0x5eed_0010, 0x5eed_0100, 0x5eed_1000];
+
+/// How the stored score relates to the true score of the position.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Bound {
+    /// The stored score is the exact score.
+    Exact,
+    /// The true score is greater than or equal to the stored score.
+    Lower,
+    /// The true score is less than or equal to the stored score.
+    Upper,
+}
+
+/// The result of a search, as stored in the table.
+#[derive(Debug, Clone, Copy)]
+pub struct Entry {
+    key: u64,
+    pub depth: u8,
+    pub bound: Bound,
+    pub score: Score,
+    pub best_move: Option<Coord>,
+}
+
+impl Entry {
+    pub fn new(depth: u8, bound: Bound, score: Score, best_move: Option<Coord>) -> Entry {
+        Entry {
+            key: 0,
+            depth,
+            bound,
+            score,
+            best_move,
+        }
+    }
+}
+
+/// A fixed-size transposition table.
+/// It can be safely shared among threads, and keeps count of its hits and misses.
+pub struct TranspositionTable {
+    cell_keys: [[[u64; BOARD_SIZE]; BOARD_SIZE]; 2],
+    light_key: u64,
+    size: usize,
+    shards: Vec<Mutex<Vec<Option<Entry>>>>,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+impl fmt::Debug for TranspositionTable {
+    /// Shows the size and the counters of the table, not its keys and entries.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,
+               "TranspositionTable {{ size: {}, hits: {}, misses: {} }}",
+               self.size,
+               self.get_hits(),
+               self.get_misses())
+    }
+}
+
+impl TranspositionTable {
+    /// Creates an empty table with room for `size` entries.
+    pub fn new(size: usize) -> TranspositionTable {
+        let size = if size == 0 { 1 } else { size };
+        let mut rng = XorShiftRng::from_seed(ZOBRIST_SEED);
+        let mut cell_keys = [[[0u64; BOARD_SIZE]; BOARD_SIZE]; 2];
+        for side_keys in &mut cell_keys {
+            for row_keys in side_keys.iter_mut() {
+                for key in row_keys.iter_mut() {
+                    *key = rng.gen::<u64>();
+                }
+            }
+        }
+        let shard_size = size.div_ceil(SHARDS);
+        TranspositionTable {
+            cell_keys,
+            light_key: rng.gen::<u64>(),
+            size,
+            shards: (0..SHARDS).map(|_| Mutex::new(vec![None; shard_size])).collect(),
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+        }
+    }
+
+    /// Computes the Zobrist hash of the position of `turn`, including the side to move.
+    pub fn hash(&self, turn: &Turn) -> u64 {
+        let mut key = match turn.get_state() {
+            Some(Side::Light) => self.light_key,
+            _ => 0,
+        };
+        for row in 0..BOARD_SIZE {
+            for col in 0..BOARD_SIZE {
+                if let Some(disk) = *turn.get_cell(Coord::new(row, col)).expect("Coordinates are within the board") {
+                    let side_index = match disk.get_side() {
+                        Side::Dark => 0,
+                        Side::Light => 1,
+                    };
+                    key ^= self.cell_keys[side_index][row][col];
+                }
+            }
+        }
+        key
+    }
+
+    /// Looks up the entry stored for the position with hash `key`.
+    pub fn probe(&self, key: u64) -> Option<Entry> {
+        let (shard, slot) = self.locate(key);
+        let entry = match self.shards[shard].lock().expect("Poisoned transposition table")[slot] {
+            Some(entry) if entry.key == key => Some(entry),
+            _ => None,
+        };
+        match entry {
+            Some(_) => self.hits.fetch_add(1, Ordering::Relaxed),
+            None => self.misses.fetch_add(1, Ordering::Relaxed),
+        };
+        entry
+    }
+
+    /// Stores `entry` for the position with hash `key`.
+    /// An entry for the same position is only replaced by one coming from a search at least as deep.
+    pub fn store(&self, key: u64, mut entry: Entry) {
+        entry.key = key;
+        let (shard, slot) = self.locate(key);
+        let mut shard = self.shards[shard].lock().expect("Poisoned transposition table");
+        match shard[slot] {
+            Some(old_entry) if old_entry.key == key && old_entry.depth > entry.depth => {}
+            _ => shard[slot] = Some(entry),
+        }
+    }
+
+    /// Empties the table and resets its counters.
+    pub fn clear(&self) {
+        for shard in &self.shards {
+            for entry in shard.lock().expect("Poisoned transposition table").iter_mut() {
+                *entry = None;
+            }
+        }
+        self.hits.store(0, Ordering::Relaxed);
+        self.misses.store(0, Ordering::Relaxed);
+    }
+
+    /// Number of entries the table has room for.
+    pub fn get_size(&self) -> usize {
+        self.size
+    }
+
+    /// Number of successful lookups since the table was created or cleared.
+    pub fn get_hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Number of failed lookups since the table was created or cleared.
+    pub fn get_misses(&self) -> usize {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    fn locate(&self, key: u64) -> (usize, usize) {
+        let index = (key % self.size as u64) as usize;
+        (index % SHARDS, index / SHARDS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Bound, Entry, TranspositionTable};
+    use ai_player::Score;
+    use reversi::board::Coord;
+    use reversi::turn::Turn;
+
+    #[test]
+    fn store_and_probe() {
+        let table = TranspositionTable::new(1 << 8);
+        let key = table.hash(&Turn::first_turn());
+        assert!(table.probe(key).is_none());
+        table.store(key, Entry::new(3, Bound::Lower, Score::Running(1.5f64), Some(Coord::new(4, 5))));
+        let entry = table.probe(key).expect("The entry was stored");
+        assert_eq!(entry.depth, 3);
+        assert_eq!(entry.bound, Bound::Lower);
+        assert_eq!(entry.score, Score::Running(1.5f64));
+        assert_eq!(entry.best_move, Some(Coord::new(4, 5)));
+        assert_eq!((table.get_hits(), table.get_misses()), (1, 1));
+    }
+
+    #[test]
+    fn deeper_entries_are_kept() {
+        let table = TranspositionTable::new(1 << 8);
+        let key = 12345u64;
+        table.store(key, Entry::new(4, Bound::Exact, Score::Ended(2), None));
+        table.store(key, Entry::new(2, Bound::Upper, Score::Ended(-2), None));
+        let entry = table.probe(key).expect("The entry was stored");
+        assert_eq!((entry.depth, entry.bound, entry.score), (4, Bound::Exact, Score::Ended(2)));
+        table.store(key, Entry::new(4, Bound::Upper, Score::Ended(-2), None));
+        let entry = table.probe(key).expect("The entry was stored");
+        assert_eq!((entry.depth, entry.bound, entry.score), (4, Bound::Upper, Score::Ended(-2)));
+    }
+
+    #[test]
+    fn colliding_positions_replace_each_other() {
+        let table = TranspositionTable::new(1 << 8);
+        let key = 7u64;
+        let other_key = key + table.get_size() as u64;
+        table.store(key, Entry::new(6, Bound::Exact, Score::Ended(0), None));
+        table.store(other_key, Entry::new(1, Bound::Exact, Score::Ended(4), None));
+        assert!(table.probe(key).is_none());
+        assert_eq!(table.probe(other_key).map(|entry| entry.score), Some(Score::Ended(4)));
+    }
+
+    #[test]
+    fn clear_empties_the_table() {
+        let table = TranspositionTable::new(1 << 8);
+        table.store(1, Entry::new(1, Bound::Exact, Score::Ended(0), None));
+        assert!(table.probe(1).is_some());
+        assert!(table.probe(2).is_none());
+        table.clear();
+        assert_eq!((table.get_hits(), table.get_misses()), (0, 0));
+        assert!(table.probe(1).is_none());
+        assert_eq!((table.get_hits(), table.get_misses()), (0, 1));
+    }
+
+    #[test]
+    fn hash_depends_on_position_and_side() {
+        let table = TranspositionTable::new(1 << 8);
+        let start = Turn::first_turn();
+        let mut after_move = start;
+        after_move.make_move(Coord::new(4, 5)).expect("f5 is legal");
+        assert_eq!(table.hash(&start), TranspositionTable::new(1 << 4).hash(&start));
+        assert!(table.hash(&start) != table.hash(&after_move));
+    }
+}
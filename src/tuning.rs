@@ -0,0 +1,292 @@
+//! Provides Texel-style tuning of the evaluation weights on a set of labelled positions.
+//!
+//! Each position's evaluation is turned into an expected result by a sigmoid,
+//! and the weights are adjusted one at a time as long as the mean squared error
+//! between expected and actual results decreases.
+//! The features of the positions are computed once: evaluations are linear in the weights
+//! (but for the normalization of the corner weights), so they are cheap to recompute as the weights change.
+//!
+//! A positions file is a text file with one position per line, in the format `<moves> <result>`,
+//! e.g. `f5d6c3d3c4 -6`. The moves lead to the position from the start of the game, in compact notation,
+//! and the result is the final disk differential of the game (Light's disks minus Dark's disks).
+//! Empty lines and lines starting with `#` are ignored.
+//!
+//! Positions can also be taken from whole games, such as those of a WTHOR database.
+
+use evaluation::{self, Evaluator, Features};
+use notation;
+use rayon::prelude::*;
+use reversi::board::Coord;
+use reversi::turn::Turn;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+/// Default number of passes over all the weights.
+pub const DEFAULT_ITERATIONS: usize = 100;
+
+/// Starting step by which the weights are adjusted.
+const INITIAL_STEP: f64 = 4f64;
+/// Tuning stops once the step gets smaller than this.
+const MIN_STEP: f64 = 0.25f64;
+
+/// Maximum number of rounds spent looking for the best scale.
+const MAX_SCALE_ROUNDS: usize = 100;
+
+/// A position together with the result of the game it was taken from.
+#[derive(Clone, Copy)]
+pub struct LabelledPosition {
+    pub turn: Turn,
+    /// 1 if Light won the game, 0 if Dark won it, 0.5 for a draw.
+    pub result: f64,
+}
+
+/// Loads labelled positions from the file at `path`.
+pub fn load_positions<P: AsRef<Path>>(path: P) -> io::Result<Vec<LabelledPosition>> {
+    read_positions(BufReader::new(File::open(path)?))
+}
+
+/// Reads labelled positions in the positions file format.
+/// Positions where the game is already ended are skipped, as they are never evaluated.
+pub fn read_positions<R: BufRead>(reader: R) -> io::Result<Vec<LabelledPosition>> {
+    let mut positions = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() != 2 {
+            return Err(invalid_data(line));
+        }
+        let moves = notation::parse_moves(fields[0]).ok_or_else(|| invalid_data(line))?;
+        let diff = fields[1].parse::<i16>().map_err(|_| invalid_data(line))?;
+        let turn = notation::replay(&Turn::first_turn(), &moves).ok_or_else(|| invalid_data(line))?;
+        if turn.get_state().is_some() {
+            positions.push(LabelledPosition {
+                               turn,
+                               result: result_value(diff),
+                           });
+        }
+    }
+    Ok(positions)
+}
+
+/// Labels every position of the game made of `moves` with its final disk differential `diff`
+/// (Light's disks minus Dark's disks). Returns `None` if any move is illegal.
+pub fn label_game(moves: &[Coord], diff: i16) -> Option<Vec<LabelledPosition>> {
+    let mut positions = Vec::with_capacity(moves.len());
+    let mut turn = Turn::first_turn();
+    for &coord in moves {
+        if turn.get_state().is_none() || turn.make_move(coord).is_err() {
+            return None;
+        }
+        if turn.get_state().is_some() {
+            positions.push(LabelledPosition {
+                               turn,
+                               result: result_value(diff),
+                           });
+        }
+    }
+    Some(positions)
+}
+
+/// The result of a game with final disk differential `diff`, as in `LabelledPosition`.
+fn result_value(diff: i16) -> f64 {
+    match diff {
+        0 => 0.5f64,
+        diff if diff > 0 => 1f64,
+        _ => 0f64,
+    }
+}
+
+/// The expected result of a position evaluated `eval`, given the `scale` of evaluations.
+fn expected_result(eval: f64, scale: f64) -> f64 {
+    1f64 / (1f64 + (-scale * eval).exp())
+}
+
+/// What the evaluation of a labelled position depends on, besides the weights.
+struct PositionFeatures {
+    features: Features,
+    corner_counts: [f64; 5],
+    result: f64,
+}
+
+impl PositionFeatures {
+    fn evaluate(&self, evaluator: &Evaluator) -> f64 {
+        let mut features = self.features;
+        features.corners = evaluator.corners.weigh(&self.corner_counts);
+        evaluator.weigh_features(&features)
+    }
+}
+
+/// Computes the features of every position.
+fn position_features(positions: &[LabelledPosition]) -> Vec<PositionFeatures> {
+    // The corner weights do not matter, as the corner regions are weighed from their counts
+    let evaluator = Evaluator::default();
+    positions.par_iter()
+        .map(|position| {
+            PositionFeatures {
+                features: evaluator.features(&position.turn).expect("Positions are within the board"),
+                corner_counts: evaluation::corner_counts(&position.turn).expect("Positions are within the board"),
+                result: position.result,
+            }
+        })
+        .collect()
+}
+
+/// Mean squared error between the results expected from the evaluations of `evaluator` and the actual ones.
+pub fn error(evaluator: &Evaluator, positions: &[LabelledPosition], scale: f64) -> f64 {
+    features_error(evaluator, &position_features(positions), scale)
+}
+
+fn features_error(evaluator: &Evaluator, positions: &[PositionFeatures], scale: f64) -> f64 {
+    if positions.is_empty() {
+        return 0f64;
+    }
+    let total: f64 = positions.par_iter()
+        .map(|position| (position.result - expected_result(position.evaluate(evaluator), scale)).powi(2))
+        .sum();
+    total / positions.len() as f64
+}
+
+/// Finds the scale which best fits the evaluations of `evaluator` to the results,
+/// so that tuning starts from the weights' current meaning.
+pub fn fit_scale(evaluator: &Evaluator, positions: &[LabelledPosition]) -> f64 {
+    features_fit_scale(evaluator, &position_features(positions))
+}
+
+fn features_fit_scale(evaluator: &Evaluator, positions: &[PositionFeatures]) -> f64 {
+    let mut best_scale = 1f64;
+    let mut best_error = features_error(evaluator, positions, best_scale);
+    let mut factor = 2f64;
+    // The number of rounds is bounded, in case the error keeps decreasing as the scale grows
+    for _ in 0..MAX_SCALE_ROUNDS {
+        if factor < 1.01f64 {
+            break;
+        }
+        let mut improved = false;
+        for &scale in &[best_scale * factor, best_scale / factor] {
+            let new_error = features_error(evaluator, positions, scale);
+            if new_error < best_error {
+                best_scale = scale;
+                best_error = new_error;
+                improved = true;
+            }
+        }
+        if !improved {
+            factor = factor.sqrt();
+        }
+    }
+    best_scale
+}
+
+/// Tunes the weights of `evaluator` on `positions`, making at most `iterations` passes over all of them.
+/// Returns the tuned evaluator, together with the error before and after tuning.
+pub fn tune(evaluator: &Evaluator, positions: &[LabelledPosition], iterations: usize) -> (Evaluator, f64, f64) {
+    let positions = position_features(positions);
+    let scale = features_fit_scale(evaluator, &positions);
+    let mut best = *evaluator;
+    let mut parameters = best.get_parameters();
+    let initial_error = features_error(&best, &positions, scale);
+    let mut best_error = initial_error;
+    let mut step = INITIAL_STEP;
+
+    for _ in 0..iterations {
+        let mut improved = false;
+        for index in 0..parameters.len() {
+            let original = parameters[index];
+            for &delta in &[step, -step] {
+                parameters[index] = original + delta;
+                let mut candidate = best;
+                candidate.set_parameters(&parameters);
+                let new_error = features_error(&candidate, &positions, scale);
+                if new_error < best_error {
+                    best = candidate;
+                    best_error = new_error;
+                    improved = true;
+                    break;
+                }
+                parameters[index] = original;
+            }
+        }
+        if !improved {
+            // Refine the search, until steps are too small to matter
+            step /= 2f64;
+            if step < MIN_STEP {
+                break;
+            }
+        }
+    }
+
+    (best, initial_error, best_error)
+}
+
+fn invalid_data(line: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("Invalid position line: {}", line))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{error, label_game, read_positions, tune, LabelledPosition};
+    use evaluation::{Evaluate, Evaluator};
+    use reversi::board::{Coord, BOARD_SIZE};
+    use reversi::turn::Turn;
+
+    /// Labels the positions of a few games, played by always making the first legal move from a different start.
+    fn labelled_positions() -> Vec<LabelledPosition> {
+        let mut positions = Vec::new();
+        for (game, &first) in ["f5", "e6", "d3", "c4"].iter().enumerate() {
+            let mut moves = vec![::notation::parse_coord(first).expect("The move is valid")];
+            let mut turn = Turn::first_turn();
+            turn.make_move(moves[0]).expect("The move is legal");
+            while moves.len() < 30 && turn.get_state().is_some() {
+                let coord = (0..BOARD_SIZE * BOARD_SIZE)
+                    .map(|index| Coord::new(index / BOARD_SIZE, index % BOARD_SIZE))
+                    .find(|&coord| turn.check_move(coord).is_ok())
+                    .expect("The side to move has a move");
+                turn.make_move(coord).expect("The move is legal");
+                moves.push(coord);
+            }
+            let diff = [10, -6, 0, 24][game];
+            positions.extend(label_game(&moves, diff).expect("The moves are legal"));
+        }
+        positions
+    }
+
+    #[test]
+    fn read_labelled_positions() {
+        let text = "# Positions\nf5d6c3 -6\n\nf5 0\n";
+        let positions = read_positions(text.as_bytes()).expect("The positions are valid");
+        assert_eq!(positions.len(), 2);
+        assert_eq!(positions[0].result, 0f64);
+        assert_eq!(positions[1].result, 0.5f64);
+        for text in &["f5d6c3\n", "f5f5 2\n", "f5 many\n"] {
+            assert!(read_positions(text.as_bytes()).is_err(), "Accepted {:?}", text);
+        }
+    }
+
+    #[test]
+    fn error_matches_evaluations() {
+        let positions = labelled_positions();
+        let evaluator = Evaluator::profile("stability").expect("The profile exists");
+        let scale = 0.05f64;
+        let expected = positions.iter()
+            .map(|position| {
+                let eval = evaluator.evaluate(&position.turn).expect("Positions are within the board");
+                (position.result - 1f64 / (1f64 + (-scale * eval).exp())).powi(2)
+            })
+            .sum::<f64>() / positions.len() as f64;
+        assert!((error(&evaluator, &positions, scale) - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn tuning_does_not_increase_the_error() {
+        let positions = labelled_positions();
+        let (tuned, initial_error, final_error) = tune(&Evaluator::default(), &positions, 3);
+        assert!(final_error <= initial_error);
+        assert!(initial_error > 0f64);
+        assert!(tuned.get_parameters().iter().all(|weight| weight.is_finite()));
+    }
+}
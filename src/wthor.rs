@@ -0,0 +1,228 @@
+//! Provides a reader of the WTHOR database format of the French Othello Federation,
+//! the standard archive of tournament games (`.wtb` files).
+//!
+//! A WTHOR file is a 16 bytes header followed by 68 bytes records, one per game.
+//! The header stores the number of games (bytes 4 to 7, little-endian), the year of the games (bytes 10 and 11)
+//! and the board size (byte 12, where 0 also means 8).
+//! A game record stores the numbers of the tournament (bytes 0 and 1) and of the players (bytes 2 to 5),
+//! Black's (that is, Dark's) final disk count and its theoretical one (bytes 6 and 7),
+//! and then 60 moves, one byte each, written as `10 * row + column` counting from 1 (e.g. 56 for `f5`).
+//! Passes are not recorded, and a 0 ends the moves of games shorter than 60 moves.
+//! Player and tournament names are stored in separate files (`WTHOR.JOU` and `WTHOR.TRN`), not read here.
+
+use reversi::board::{BOARD_SIZE, Coord};
+use reversi::turn::Turn;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+
+/// Size of the header of a WTHOR file, in bytes.
+const HEADER_SIZE: usize = 16;
+/// Size of a game record, in bytes.
+const GAME_SIZE: usize = 68;
+/// Number of move bytes in a game record.
+const MAX_MOVES: usize = 60;
+
+/// A game from a WTHOR file.
+#[derive(Debug, Clone)]
+pub struct WthorGame {
+    /// Index of the tournament in `WTHOR.TRN`.
+    pub tournament: u16,
+    /// Indexes of the players in `WTHOR.JOU`.
+    pub dark_player: u16,
+    pub light_player: u16,
+    /// Dark's disks at the end of the game, with the empty cells counted for the winner.
+    pub dark_disks: u8,
+    /// Dark's disks at the end of the game if both players played perfectly from some point on, as computed by the archive.
+    pub theoretical_dark_disks: u8,
+    pub moves: Vec<Coord>,
+}
+
+impl WthorGame {
+    /// The final disk differential of the game (Light's disks minus Dark's disks).
+    pub fn result(&self) -> i16 {
+        (BOARD_SIZE * BOARD_SIZE) as i16 - 2 * self.dark_disks as i16
+    }
+}
+
+/// An iterator over the games of a WTHOR file.
+pub struct WthorReader<R> {
+    reader: R,
+    /// The year of the games in the file.
+    year: u16,
+    /// Games still to be read.
+    remaining: u32,
+}
+
+impl WthorReader<BufReader<File>> {
+    /// Opens the WTHOR file at `path`.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<WthorReader<BufReader<File>>> {
+        WthorReader::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> WthorReader<R> {
+    /// Reads the header of a WTHOR file from `reader`, which is then ready to read the games.
+    pub fn new(mut reader: R) -> io::Result<WthorReader<R>> {
+        let mut header = [0u8; HEADER_SIZE];
+        reader.read_exact(&mut header)?;
+        if header[12] != 0 && header[12] as usize != BOARD_SIZE {
+            return Err(invalid_data("Unsupported board size"));
+        }
+        Ok(WthorReader {
+               reader,
+               year: read_u16(&header[10..12]),
+               remaining: read_u32(&header[4..8]),
+           })
+    }
+
+    /// The year of the games in the file.
+    pub fn year(&self) -> u16 {
+        self.year
+    }
+
+    /// Reads the next game record, checking that its moves are legal.
+    fn read_game(&mut self) -> io::Result<WthorGame> {
+        let mut record = [0u8; GAME_SIZE];
+        self.reader.read_exact(&mut record)?;
+        let mut moves = Vec::with_capacity(MAX_MOVES);
+        let mut turn = Turn::first_turn();
+        for &byte in record[GAME_SIZE - MAX_MOVES..].iter().take_while(|&&byte| byte != 0) {
+            let (row, col) = (byte as usize / 10, byte as usize % 10);
+            if !(1..=BOARD_SIZE).contains(&row) || !(1..=BOARD_SIZE).contains(&col) {
+                return Err(invalid_data("Invalid move"));
+            }
+            let coord = Coord::new(row - 1, col - 1);
+            turn.make_move(coord).map_err(|_| invalid_data("Illegal move"))?;
+            moves.push(coord);
+        }
+        Ok(WthorGame {
+               tournament: read_u16(&record[0..2]),
+               dark_player: read_u16(&record[2..4]),
+               light_player: read_u16(&record[4..6]),
+               dark_disks: record[6],
+               theoretical_dark_disks: record[7],
+               moves,
+           })
+    }
+}
+
+impl<R: Read> Iterator for WthorReader<R> {
+    type Item = io::Result<WthorGame>;
+
+    /// Reads the next game, or an error if the record is invalid.
+    /// An invalid record still counts as a game, so the following ones can be read.
+    fn next(&mut self) -> Option<io::Result<WthorGame>> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let game = self.read_game();
+        if let Err(ref err) = game {
+            // Games cannot be read past a truncated or unreadable file
+            if err.kind() != io::ErrorKind::InvalidData {
+                self.remaining = 0;
+            }
+        }
+        Some(game)
+    }
+}
+
+/// Loads all the games of the WTHOR file at `path`, skipping the invalid ones.
+pub fn load_games<P: AsRef<Path>>(path: P) -> io::Result<Vec<WthorGame>> {
+    let mut games = Vec::new();
+    for game in WthorReader::open(path)? {
+        match game {
+            Ok(game) => games.push(game),
+            Err(ref err) if err.kind() == io::ErrorKind::InvalidData => continue,
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(games)
+}
+
+/// Whether the file at `path` is a WTHOR file, according to its extension in any case (e.g. `WTH_2017.wtb`).
+pub fn is_wthor_file<P: AsRef<Path>>(path: P) -> bool {
+    path.as_ref()
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| extension.to_lowercase() == "wtb")
+}
+
+/// Reads a little-endian 16 bits number.
+fn read_u16(bytes: &[u8]) -> u16 {
+    bytes[0] as u16 | (bytes[1] as u16) << 8
+}
+
+/// Reads a little-endian 32 bits number.
+fn read_u32(bytes: &[u8]) -> u32 {
+    read_u16(&bytes[0..2]) as u32 | (read_u16(&bytes[2..4]) as u32) << 16
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("Invalid WTHOR game: {}", message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_wthor_file, WthorReader, GAME_SIZE, HEADER_SIZE};
+    use notation;
+    use std::io;
+
+    /// A WTHOR file of 2017 claiming `games` games, with a valid record and one with an illegal move.
+    fn wthor_file(games: u8) -> Vec<u8> {
+        let mut file = vec![0u8; HEADER_SIZE];
+        file[4] = games;
+        file[10] = (2017 % 256) as u8;
+        file[11] = (2017 / 256) as u8;
+        file[12] = 8;
+        let mut valid = vec![3, 1, 5, 0, 7, 0, 40, 38];
+        valid.extend_from_slice(&[56, 64, 33]);
+        valid.resize(GAME_SIZE, 0);
+        let mut invalid = vec![3, 0, 7, 0, 5, 0, 33, 33];
+        invalid.extend_from_slice(&[56, 56]);
+        invalid.resize(GAME_SIZE, 0);
+        file.extend(valid);
+        file.extend(invalid);
+        file
+    }
+
+    #[test]
+    fn read_games() {
+        let file = wthor_file(2);
+        let mut reader = WthorReader::new(&file[..]).expect("The header is valid");
+        assert_eq!(reader.year(), 2017);
+        let game = reader.next().expect("There is a first game").expect("The first game is valid");
+        assert_eq!((game.tournament, game.dark_player, game.light_player), (259, 5, 7));
+        assert_eq!((game.dark_disks, game.theoretical_dark_disks), (40, 38));
+        assert_eq!(game.result(), -16);
+        assert_eq!(game.moves, notation::parse_moves("f5d6c3").expect("The moves are valid"));
+        let err = reader.next().expect("There is a second game").expect_err("The second game is invalid");
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn stop_at_the_end_of_the_file() {
+        let file = wthor_file(5);
+        let games: Vec<io::Result<_>> = WthorReader::new(&file[..]).expect("The header is valid").collect();
+        assert_eq!(games.len(), 3);
+        assert_eq!(games[2].as_ref().expect_err("The file is truncated").kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn reject_other_board_sizes() {
+        let mut file = wthor_file(2);
+        file[12] = 10;
+        assert!(WthorReader::new(&file[..]).is_err());
+        assert!(WthorReader::new(&file[..HEADER_SIZE - 1]).is_err());
+    }
+
+    #[test]
+    fn wthor_extension() {
+        assert!(is_wthor_file("WTH_2017.wtb"));
+        assert!(is_wthor_file("games/WTH_2017.WTB"));
+        assert!(!is_wthor_file("games.txt"));
+        assert!(!is_wthor_file("wtb"));
+    }
+}
@@ -5,8 +5,10 @@ extern crate rayon;
 extern crate reversi;
 extern crate rusthello_lib;
 
-use reversi::game::Game;
-use rusthello_lib::ai_player::AiPlayer;
+use reversi::game::{Game, PlayerAction};
+use rusthello_lib::ai_player::{AiPlayer, AlphaBeta, Strength};
+use rusthello_lib::evaluation::Evaluator;
+use rusthello_lib::mcts_player::{Budget, MctsPlayer, PlayoutPolicy};
 use std::cmp::Ordering;
 
 mod test_ai;
@@ -14,11 +16,11 @@ mod test_ai;
 #[test]
 fn test_ai() {
 
-    let adam = AiPlayer::Weak;
+    let adam = AiPlayer::new(Strength::Weak);
     let mut adam_wins = 0;
     let mut adam_total_score: u64 = 0;
 
-    let eve = test_ai::ExpAiPlayer{};
+    let eve = test_ai::exp_ai_player();
     let mut eve_wins = 0;
     let mut eve_total_score: u64 = 0;
 
@@ -67,3 +69,25 @@ fn test_ai() {
              eve_total_score);
     println!("Tied {} games\n", ties);
 }
+
+#[test]
+fn test_seeded_ai() {
+
+    // Plays a whole game between two seeded players and records its moves
+    let play_seeded_game = || {
+        let adam = AiPlayer::compose(AlphaBeta::seeded(Strength::Weak, 42, 0.05f64), Evaluator::default());
+        let eve = MctsPlayer::seeded(Budget::Playouts(200), PlayoutPolicy::CornersFirst, 42);
+        let mut game = Game::new(&adam, &eve);
+        let mut moves = Vec::new();
+        while !game.is_endgame() {
+            match game.play_turn().expect("`play_turn` returned an error") {
+                PlayerAction::Move(coord) => moves.push(coord),
+                _ => panic!("AI players only move"),
+            }
+        }
+        moves
+    };
+
+    // The same seeds have to give the same game
+    assert!(play_seeded_game() == play_seeded_game());
+}
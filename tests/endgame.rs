@@ -0,0 +1,141 @@
+//! Test module for the endgame solver.
+
+extern crate reversi;
+extern crate rusthello_lib;
+
+use reversi::Side;
+use reversi::turn::Turn;
+use rusthello_lib::ai_player::{self, AlphaBeta, Score, Strength};
+use rusthello_lib::endgame_solver::{self, SolveMode};
+use rusthello_lib::evaluation::Evaluator;
+use rusthello_lib::notation;
+use rusthello_lib::transposition_table::TranspositionTable;
+use std::time::{Duration, Instant};
+
+#[test]
+fn test_endgame_solver() {
+
+    // Reach an endgame position
+    let search = AlphaBeta::new(Strength::Weak);
+    let evaluator = Evaluator::default();
+    let table = TranspositionTable::new(1 << 12);
+    let mut turn = Turn::first_turn();
+    while ai_player::empty_cells(&turn) > 10 {
+        let coord = search.find_best_move(&turn, 1, &table, &evaluator).expect("`find_best_move` returned an error");
+        turn.make_move(coord).expect("`make_move` returned an error");
+    }
+
+    let exact = endgame_solver::solve(&turn, SolveMode::Exact).expect("`solve` returned an error");
+    let wld = endgame_solver::solve(&turn, SolveMode::WinLossDraw).expect("`solve` returned an error");
+    let final_diff = match exact.score {
+        Score::Ended(diff) => diff,
+        Score::Running(_) => panic!("The solver returned a heuristic score"),
+    };
+    assert_eq!(wld.score, Score::Ended(final_diff.signum()));
+
+    // The analysis has to agree with the solver and rank every legal move
+    let analysis = AlphaBeta::new(Strength::Strong)
+        .analyze_to_depth(&turn, 1, &table, &evaluator)
+        .expect("`analyze_to_depth` returned an error");
+    assert_eq!(analysis.moves.len(), ai_player::legal_moves(&turn).len());
+    assert_eq!(analysis.moves[0].score, exact.score);
+    for (move1, move2) in analysis.moves.iter().zip(analysis.moves.iter().skip(1)) {
+        match turn.get_state() {
+            Some(Side::Light) => assert!(move1.score >= move2.score),
+            _ => assert!(move1.score <= move2.score),
+        }
+    }
+    for move_analysis in &analysis.moves {
+        assert!(move_analysis.variation[0] == move_analysis.coord);
+        let mut turn_in_variation = turn;
+        for &coord in &move_analysis.variation {
+            turn_in_variation.make_move(coord).expect("The variation contains an illegal move");
+        }
+    }
+
+    // Playing the solver's moves the proven score has to be reached
+    while turn.get_state().is_some() {
+        let solution = endgame_solver::solve(&turn, SolveMode::Exact).expect("`solve` returned an error");
+        assert_eq!(solution.score, exact.score);
+        turn.make_move(solution.best_move.expect("The game is not ended"))
+            .expect("`make_move` returned an error");
+    }
+    assert_eq!(turn.get_score_diff(), final_diff);
+}
+
+#[test]
+fn test_endgame_from_position() {
+
+    // Set up an endgame position with 10 empty cells, Dark to move
+    let position = "-XOOO-O-OOOO-OO-OOOXOXOXOXOOOXO-OXOOOXOOOOXXXXOO-OXXXXXO--OX-XXX X";
+    let turn = notation::parse_position(position).expect("`parse_position` rejected a valid position");
+    assert_eq!(notation::position_to_string(&turn), position);
+    assert_eq!(turn.get_state(), Some(Side::Dark));
+    assert_eq!(ai_player::empty_cells(&turn), 10);
+
+    // The analysis has to agree with the solver
+    let exact = endgame_solver::solve(&turn, SolveMode::Exact).expect("`solve` returned an error");
+    let analysis = AlphaBeta::new(Strength::Strong)
+        .analyze_to_depth(&turn, 1, &TranspositionTable::new(1 << 12), &Evaluator::default())
+        .expect("`analyze_to_depth` returned an error");
+    assert_eq!(analysis.moves[0].score, exact.score);
+
+    // A side with no legal moves passes
+    let position = "OOOOOXO-OOXXXXOOOXXXXXXOOOXOXXXOOOOOOXXOOXOXOXOOOXXOOOOOOOOOOOOO O";
+    let turn = notation::parse_position(position).expect("`parse_position` rejected a valid position");
+    assert_eq!(turn.get_state(), Some(Side::Dark));
+}
+
+#[test]
+fn test_endgame_by_strength() {
+
+    // Weak and medium searches keep searching to a fixed depth in an endgame of 10 empty cells
+    let position = "-XOOO-O-OOOO-OO-OOOXOXOXOXOOOXO-OXOOOXOOOOXXXXOO-OXXXXXO--OX-XXX X";
+    let turn = notation::parse_position(position).expect("`parse_position` rejected a valid position");
+    let evaluator = Evaluator::default();
+    for &strength in &[Strength::Weak, Strength::Medium] {
+        let search = AlphaBeta::new(strength);
+        let (_, score) = search.best_move_and_score(&turn, &evaluator).expect("`best_move_and_score` returned an error");
+        match score {
+            Score::Running(_) => {}
+            Score::Ended(_) => panic!("A {:?} search solved the endgame", strength),
+        }
+        assert!(search.table().get_hits() + search.table().get_misses() > 0);
+    }
+
+    // Strong searches solve it exactly, without using the table
+    let exact = endgame_solver::solve(&turn, SolveMode::Exact).expect("`solve` returned an error");
+    let search = AlphaBeta::new(Strength::Strong);
+    let (_, score) = search.best_move_and_score(&turn, &evaluator).expect("`best_move_and_score` returned an error");
+    assert_eq!(score, exact.score);
+    assert_eq!(search.table().get_hits() + search.table().get_misses(), 0);
+
+    // The switch can be set for each search
+    let search = AlphaBeta::new(Strength::Weak).with_endgame_empty_cells(10);
+    let (_, score) = search.best_move_and_score(&turn, &evaluator).expect("`best_move_and_score` returned an error");
+    assert_eq!(score, exact.score);
+    let search = AlphaBeta::new(Strength::Strong).with_endgame_empty_cells(0);
+    let (_, score) = search.best_move_and_score(&turn, &evaluator).expect("`best_move_and_score` returned an error");
+    assert!(score != exact.score);
+}
+
+#[test]
+fn test_endgame_time_limit() {
+
+    // Solving the whole game cannot be done in time, and the solver gives up right away
+    let start = Instant::now();
+    let solution = endgame_solver::solve_in_time(&Turn::first_turn(), SolveMode::Exact, Duration::from_millis(50))
+        .expect("`solve_in_time` returned an error");
+    assert!(solution.is_none());
+    assert!(start.elapsed() < Duration::from_secs(5));
+
+    // With enough time, the solution is the same as without a limit
+    let position = "-XOOO-O-OOOO-OO-OOOXOXOXOXOOOXO-OXOOOXOOOOXXXXOO-OXXXXXO--OX-XXX X";
+    let turn = notation::parse_position(position).expect("`parse_position` rejected a valid position");
+    let exact = endgame_solver::solve(&turn, SolveMode::Exact).expect("`solve` returned an error");
+    let timed = endgame_solver::solve_in_time(&turn, SolveMode::Exact, Duration::from_secs(60))
+        .expect("`solve_in_time` returned an error")
+        .expect("The position can be solved in time");
+    assert_eq!(timed.score, exact.score);
+    assert_eq!(timed.best_move, exact.best_move);
+}